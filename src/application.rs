@@ -1,112 +1,1236 @@
+pub mod dotenv;
+pub mod environment;
+mod importer;
 mod runner;
+mod scaffold;
 mod synchronizer;
 
 use crate::config;
+use crate::dns::record::Record;
+use crate::docker_compose::{self, engine};
+use crate::migration;
+use crate::secret;
+use dotenv::Layer;
+use environment::{Attribute, Reference};
 use garde::Validate;
+use once_cell::sync::Lazy;
+use path_absolutize::Absolutize;
+use regex::Regex;
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
-use std::fs::{self, File};
+use serde_json::Value as JsonValue;
+use serde_with::skip_serializing_none;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
 use std::io::Write;
-use std::path::PathBuf;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process;
 
 pub struct Application {
   pub name: String,
   values: Values,
 }
 
-#[derive(Serialize, Deserialize, Validate)]
-struct Values {
+/// Version of the schema of the application TOML.
+pub const VERSION: u32 = 1;
+
+static PORT: Lazy<Regex> = Lazy::new(|| Regex::new(r"^([0-9.]+:)?[0-9]+(-[0-9]+)?:[0-9]+(-[0-9]+)?(/(tcp|udp))?$").unwrap());
+
+const SYNC_MODES: [&str; 4] = ["two-way-safe", "two-way-resolved", "one-way-safe", "one-way-replica"];
+
+static SYNC_MODE: Lazy<Regex> = Lazy::new(|| Regex::new(&format!("^({})$", SYNC_MODES.join("|"))).unwrap());
+
+/// Env files under `env/` that are ignored by git. The local values are of the user, and the secrets are encrypted with the key of the user.
+const ENV_IGNORES: [&str; 2] = ["*.local.env", "*.secret.env"];
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Default)]
+pub struct Values {
+  /// Version of the schema.
+  #[garde(range(min = 1, max = VERSION))]
+  #[schemars(range(min = 1, max = "VERSION"), default = "default_version")]
+  version: Option<u32>,
+
+  /// Relative path to the repository directory from not applications directory but root.
   #[garde(required, length(min = 1))]
+  #[schemars(required, length(min = 1))]
   path: Option<String>,
 
+  /// URL for the repository
   #[garde(skip)]
   repository: Option<String>,
+
+  /// Applications that are updated before this application is up.
+  #[garde(skip)]
+  depends_on: Option<Vec<String>>,
+
+  /// Whether the services are published under the domain of the workspace too. (e.g. postgres.local)
+  #[garde(skip)]
+  shared: Option<bool>,
+
+  /// Shared applications that have to be running before this application is up.
+  #[garde(skip)]
+  uses: Option<Vec<String>>,
+
+  /// Applications allowed to connect to the services. Default is all of them.
+  #[garde(skip)]
+  allow_from: Option<Vec<String>>,
+
+  /// Compose files and the services that aren't started.
+  #[garde(dive)]
+  compose: Option<ComposeValues>,
+
+  /// Ports published to the host by the service name. (e.g. "8080:80")
+  #[garde(custom(is_ports))]
+  ports: Option<BTreeMap<String, Vec<String>>>,
+
+  #[garde(dive)]
+  hooks: Option<HooksValues>,
+
+  /// File synchronization into the VM by mutagen.
+  #[garde(dive)]
+  sync: Option<SyncValues>,
+
+  #[garde(dive)]
+  resources: Option<ResourcesValues>,
+
+  /// Templates of the environment variables injected into the services.
+  #[garde(skip)]
+  env: Option<BTreeMap<String, String>>,
+
+  /// Extra DNS records and hostname aliases of the services.
+  #[garde(dive)]
+  dns: Option<DnsValues>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate)]
+struct ComposeValues {
+  /// Compose files relative to the repository.
+  #[garde(length(min = 1), inner(inner(length(min = 1))))]
+  #[schemars(length(min = 1), inner(length(min = 1)), default = "default_compose_files")]
+  files: Option<Vec<String>>,
+  /// Services that aren't started.
+  #[garde(skip)]
+  skip: Option<Vec<String>>,
+}
+
+/// Commands run with `sh -c` in the repository directory.
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate)]
+struct HooksValues {
+  #[garde(inner(inner(length(min = 1))))]
+  #[schemars(inner(length(min = 1)))]
+  pre_up: Option<Vec<String>>,
+  #[garde(inner(inner(length(min = 1))))]
+  #[schemars(inner(length(min = 1)))]
+  post_up: Option<Vec<String>>,
+  /// Run when the files under `.dist/<app>` are regenerated.
+  #[garde(inner(inner(length(min = 1))))]
+  #[schemars(inner(length(min = 1)))]
+  post_update: Option<Vec<String>>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate)]
+struct SyncValues {
+  /// Whether the repository is synchronized into the VM by mutagen.
+  #[garde(skip)]
+  #[schemars(default = "default_sync_enabled")]
+  enabled: Option<bool>,
+  /// One of two-way-safe, two-way-resolved, one-way-safe or one-way-replica.
+  #[garde(custom(is_sync_mode))]
+  #[schemars(regex(path = "SYNC_MODE"))]
+  mode: Option<String>,
+  /// Paths ignored in addition to the default ones.
+  #[garde(skip)]
+  ignore: Option<Vec<String>>,
+}
+
+/// Limits applied to each service. The ones in `services` override them by the service name.
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Clone, Default)]
+pub struct ResourcesValues {
+  #[serde(flatten)]
+  #[garde(dive)]
+  pub defaults: LimitsValues,
+  /// Limits by the service name.
+  #[garde(dive)]
+  pub services: Option<BTreeMap<String, LimitsValues>>,
+}
+
+/// Limits of the container.
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Clone, Default, Debug, PartialEq)]
+pub struct LimitsValues {
+  #[garde(range(min = 0.01))]
+  #[schemars(range(min = 0.01))]
+  pub cpus: Option<f64>,
+  /// Memory size. (e.g. "512m", "1g")
+  #[garde(custom(config::is_size))]
+  #[schemars(regex(path = "config::SIZE"))]
+  pub mem_limit: Option<String>,
+  #[garde(range(min = 1))]
+  #[schemars(range(min = 1))]
+  pub pids_limit: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate)]
+struct DnsValues {
+  /// Extra records under the domain of the application.
+  #[garde(dive)]
+  records: Option<Vec<Record>>,
+  /// Hostnames under the domain of the application by the service name.
+  #[garde(skip)]
+  aliases: Option<BTreeMap<String, Vec<String>>>,
+}
+
+fn default_version() -> Option<u32> {
+  return Some(VERSION);
+}
+
+fn default_compose_files() -> Option<Vec<String>> {
+  return Some(vec!["docker-compose.yml".to_string()]);
+}
+
+fn default_sync_enabled() -> Option<bool> {
+  return Some(true);
+}
+
+fn is_ports(value: &Option<BTreeMap<String, Vec<String>>>, _: &()) -> garde::Result {
+  for (service, ports) in value.iter().flatten() {
+    if let Some(p) = ports.iter().find(|p| !PORT.is_match(p)) {
+      return Err(garde::Error::new(format!("`{}` of {} is not a port like 8080:80", p, service)));
+    }
+  }
+
+  return Ok(());
+}
+
+fn is_sync_mode(value: &Option<String>, _: &()) -> garde::Result {
+  match value {
+    Some(v) if !SYNC_MODE.is_match(v) => Err(garde::Error::new(format!("`{}` is not one of {}", v, SYNC_MODES.join(", ")))),
+    _ => Ok(()),
+  }
 }
 
 pub fn is_exists(name: &str) -> bool {
-  return file_path(name).exists();
+  return config::current().try_resolve(name).is_some();
+}
+
+/// Name of the application whose path contains the directory. The innermost one is chosen if the paths are nested.
+pub fn infer_from(dir: &Path) -> Option<String> {
+  let dir = dir.canonicalize().ok()?;
+
+  return config::current()
+    .application_names()
+    .into_iter()
+    .filter_map(|name| {
+      let root = load(&name).ok()?.root().canonicalize().ok()?;
+
+      dir.starts_with(&root).then(|| (root.components().count(), name))
+    })
+    .max()
+    .map(|(_, name)| name);
 }
 
 pub fn find_by(name: &str) -> Application {
+  return load(name).unwrap_or_else(|e| panic!("{}", e));
+}
+
+/// Load the application and validate it with the rules of the schema.
+pub fn load(name: &str) -> Result<Application, String> {
   let f = file_path(name);
-  let s = fs::read_to_string(*f.clone()).expect(&format!("Failed to read {:?}", f));
-  let v: Values = toml::from_str(&s).expect(&format!("Failed to load config from {:?}", f));
+  let s = fs::read_to_string(*f.clone()).map_err(|e| format!("Failed to read {:?} : {}", f, e))?;
+  // The old file is migrated in memory until it's rewritten by `hills migrate`.
+  let s = migration::migrate_str(migration::Schema::Application, &s, VERSION).map_err(|e| format!("Failed to load config from {:?} : {}", f, e))?;
+  let v: Values = toml::from_str(&s).map_err(|e| format!("Failed to load config from {:?} : {}", f, e))?;
 
   if let Err(e) = v.validate(&()) {
-    panic!("Invalid application {name} : {e}");
+    return Err(format!("Invalid application {name} : {}", e.to_string().trim_end()));
   }
 
-  return Application { name: name.to_string(), values: v };
+  return Ok(Application { name: name.to_string(), values: v });
+}
+
+/// JSON Schema of the application TOML with the doc comments and the constraints.
+pub fn schema() -> RootSchema {
+  let mut schema = schema_for!(Values);
+
+  schema.schema.metadata().title = Some("Application".to_string());
+
+  return schema;
 }
 
 pub fn create(name: &str) {
-  let f = file_path(name);
-  let mut fs = File::create(*f).unwrap();
-  write!(fs, "{}", template()).unwrap();
-  fs.flush().unwrap();
+  let f = Box::new(config::current().app_root().join(format!("{}.toml", name)));
+
+  config::is_label(name).unwrap_or_else(|e| panic!("{}", e));
+
+  if f.exists() {
+    panic!("Already exists {:?}", f);
+  }
+
+  config::create_file(f, template());
+}
+
+/// Create the application from the named template. The starter files are written into the repository unless they exist.
+pub fn create_from(name: &str, template: &str, vars: BTreeMap<String, String>) {
+  let c = config::current();
+  let f = Box::new(c.app_root().join(format!("{}.toml", name)));
+
+  config::is_label(name).unwrap_or_else(|e| panic!("{}", e));
+
+  if f.exists() || c.aliases().contains_key(name) {
+    panic!("Already exists {}", name);
+  }
+
+  let dir = scaffold::find(template).unwrap_or_else(|e| panic!("{}", e));
+  let mut vars = vars;
+
+  vars.entry("name".to_string()).or_insert(name.to_string());
+  vars.entry("domain".to_string()).or_insert(format!("{}.{}", name, c.domain()));
+
+  let s = scaffold::application(&dir, &vars).unwrap_or_else(|e| panic!("{}", e));
+  let v: Values = toml::from_str(&s).unwrap_or_else(|e| panic!("Invalid template {} : {}", template, e));
+
+  if let Err(e) = v.validate(&()) {
+    panic!("Invalid template {} : {}", template, e.to_string().trim_end());
+  }
+
+  let files = scaffold::files(&dir, &vars).unwrap_or_else(|e| panic!("{}", e));
+
+  if !files.is_empty() && v.path.as_ref().is_none_or(|p| p.is_empty()) {
+    panic!("Invalid template {} : `path` is required to write the files", template);
+  }
+
+  config::create_file(f, s);
+
+  for (path, content, permissions) in files {
+    let to = c.root().join(v.path.as_ref().unwrap()).join(path);
+
+    if to.exists() {
+      println!("Skipped {:?} that already exists", to);
+      continue;
+    }
+
+    fs::create_dir_all(to.parent().unwrap()).expect(&format!("Failed to create {:?}", to.parent()));
+    config::create_file(Box::new(to.clone()), content);
+    fs::set_permissions(&to, permissions).expect(&format!("Failed to set the permissions of {:?}", to));
+  }
+}
+
+/// Names of the templates for `hills new --template`.
+pub fn template_names() -> Vec<String> {
+  return scaffold::names();
+}
+
+/// Import the local checkout as an application. The name is derived from the directory unless it's given.
+pub fn import(dir: &Path, name: Option<&str>) -> Result<String, String> {
+  let c = config::current();
+  let dir = dir.canonicalize().map_err(|e| format!("Failed to find {:?} : {}", dir, e))?;
+  let name = name.map(|n| n.to_string()).unwrap_or_else(|| importer::name_of(&dir));
+
+  config::is_label(&name)?;
+
+  if !c.app_root().exists() {
+    fs::create_dir_all(*c.app_root()).expect(&format!("Failed to create {:?}", c.app_root()));
+  }
+
+  if c.try_resolve(&name).is_some() || c.aliases().contains_key(&name) {
+    return Err(format!("Already exists {}", name));
+  }
+
+  if let Some(other) = c.application_names().into_iter().find(|n| load(n).is_ok_and(|a| a.root().canonicalize().is_ok_and(|r| r == dir))) {
+    return Err(format!("{:?} is already imported as {}", dir, other));
+  }
+
+  let detected = importer::detect(&dir)?;
+  let root = c.root().canonicalize().map_err(|e| format!("Failed to find {:?} : {}", c.root(), e))?;
+  let mut doc = template().parse::<toml_edit::Document>().unwrap();
+
+  doc["path"] = toml_edit::value(importer::relative_path(&dir, &root).to_string_lossy().to_string());
+
+  if let Some(repository) = detected.repository.as_ref() {
+    doc["repository"] = toml_edit::value(repository.clone());
+  }
+
+  if Some(detected.files.clone()) != default_compose_files() {
+    doc["compose"] = toml_edit::table();
+    doc["compose"]["files"] = toml_edit::value(detected.files.iter().collect::<toml_edit::Array>());
+  }
+
+  // The empty URL is commented out like the optional keys of the template.
+  let content = match detected.repository {
+    Some(_) => doc.to_string(),
+    None => doc.to_string().replacen("\nrepository = \"\"\n", "\n# repository = \"\"\n", 1),
+  };
+
+  config::create_file(Box::new(c.app_root().join(format!("{}.toml", name))), content);
+
+  println!("Imported {} from {:?}", name, dir);
+  println!("  files: {}", detected.files.join(", "));
+
+  for (service, ports) in detected.services.iter() {
+    match ports.is_empty() {
+      true => println!("  {}", service),
+      false => println!("  {} : {}", service, ports.join(", ")),
+    }
+  }
+
+  if detected.repository.is_none() {
+    eprintln!("Warning: Not found the remote of the repository. Fill in `repository` of {}", name);
+  }
+
+  for w in detected.warnings.iter() {
+    eprintln!("Warning: {}", w);
+  }
+
+  return Ok(name);
+}
+
+/// Repositories under the directory that can be imported. The repository of the root and the imported ones are excluded.
+pub fn scan(dir: &Path) -> Vec<PathBuf> {
+  let c = config::current();
+  let root = c.root().canonicalize().unwrap_or(c.root().to_path_buf());
+  let imported = c.application_names().iter().filter_map(|n| load(n).ok()?.root().canonicalize().ok()).collect::<Vec<PathBuf>>();
+  let dir = match dir.canonicalize() {
+    Ok(dir) => dir,
+    Err(_) => return vec![],
+  };
+
+  return importer::scan(&dir, &root).into_iter().filter(|repo| !imported.contains(repo)).collect();
 }
 
+/// Rename the application. The TOML, the aliases, the env files, the subnets, the leases, `.dist/<app>` and the references of the other applications follow it.
+pub fn rename(name: &str, new_name: &str) {
+  let c = config::current();
+  let from = c.resolve(name);
+
+  config::is_label(new_name).unwrap_or_else(|e| panic!("{}", e));
+
+  if c.try_resolve(new_name).is_some() || c.aliases().contains_key(new_name) {
+    panic!("Already exists {}", new_name);
+  }
+
+  let app = find_by(&from);
+
+  // The containers are named after the application. They'd be left behind with the old name.
+  if app.is_running() {
+    panic!("{} is running. Stop the containers first", from);
+  }
+
+  let mut moves: Vec<(Box<PathBuf>, PathBuf)> = vec![(file_path(&from), c.app_root().join(format!("{}.toml", new_name)))];
+
+  for layer in [Layer::Workspace, Layer::Local, Layer::Secret] {
+    let f = app.env_file_of(layer).unwrap();
+    let file_name = f.file_name().unwrap().to_str().unwrap().strip_prefix(from.as_str()).unwrap().to_string();
+    let to = f.with_file_name(format!("{}{}", new_name, file_name));
+
+    moves.push((f, to));
+  }
+
+  moves.push((app.dist_root(), c.root().join(".dist").join(new_name)));
+
+  for (f, to) in moves.into_iter().filter(|(f, _)| f.exists()) {
+    fs::rename(f.as_ref(), &to).expect(&format!("Failed to move {:?} to {:?}", f, to));
+    println!("Moved {:?} to {:?}", f, to);
+  }
+
+  // The copies of the compose files are removed to regenerate the files with the new name.
+  let dist = c.root().join(".dist").join(new_name);
+
+  if dist.exists() {
+    for entry in fs::read_dir(&dist).expect(&format!("Failed to read {:?}", dist)) {
+      let path = entry.unwrap().path();
+
+      if path.extension().is_some_and(|e| e == "yml") {
+        fs::remove_file(&path).expect(&format!("Failed to remove {:?}", path));
+      }
+    }
+  }
+
+  config::rename_aliases(&from, new_name);
+  c.dns().rename(&from, new_name);
+
+  for other in c.application_names().into_iter().filter(|n| n != new_name) {
+    rename_references(c.app_root().join(format!("{}.toml", other)), &from, new_name);
+  }
+
+  println!("Renamed {} to {}. Run `hills up {}` to regenerate the files", from, new_name, new_name);
+}
+
+/// Rewrite `depends_on`, `uses`, `allow_from` and the templates of `[env]` that reference the application.
+fn rename_references(f: PathBuf, from: &str, to: &str) {
+  let s = fs::read_to_string(&f).expect(&format!("Failed to read {:?}", f));
+  let mut doc = s.parse::<toml_edit::Document>().expect(&format!("Failed to parse {:?}", f));
+  // The literal strings are kept literal not to escape the quotes of the templates.
+  let replace = |v: &mut toml_edit::Value, s: String| {
+    let decor = v.decor().clone();
+    let literal = v.to_string().trim_start().starts_with('\'') && !s.contains('\'');
+
+    *v = if literal { format!("'{}'", s).parse().unwrap() } else { toml_edit::Value::from(s) };
+    *v.decor_mut() = decor;
+  };
+
+  for key in ["depends_on", "uses", "allow_from"] {
+    if let Some(list) = doc.get_mut(key).and_then(|d| d.as_array_mut()) {
+      for v in list.iter_mut().filter(|v| v.as_str() == Some(from)) {
+        replace(v, to.to_string());
+      }
+    }
+  }
+
+  if let Some(env) = doc.get_mut("env").and_then(|e| e.as_table_like_mut()) {
+    let pattern = format!("app(\"{}\")", from);
+
+    for (_, item) in env.iter_mut() {
+      if let Some(v) = item.as_value_mut().filter(|v| v.as_str().is_some_and(|t| t.contains(&pattern))) {
+        let rendered = v.as_str().unwrap().replace(&pattern, &format!("app(\"{}\")", to));
+
+        replace(v, rendered);
+      }
+    }
+  }
+
+  let updated = doc.to_string();
+
+  if updated != s {
+    config::create_file(Box::new(f), updated);
+  }
+}
+
+/// Keys written without the comment in the template.
+const REQUIRED: [&str; 3] = ["version", "path", "repository"];
+
+/// Template of `hills new` generated from the schema. The keys are commented out with the defaults except for the required ones.
 pub fn template() -> String {
-  return r#"# Relative path to the repository directory from not applications directory but root.
-path = ""
+  let schema = serde_json::to_value(schema()).unwrap();
+  let mut lines: Vec<String> = vec![];
+
+  push_table(&schema, &schema, None, &mut lines);
+
+  return lines.join("\n") + "\n";
+}
+
+/// Push the keys of the object. The tables follow the values as TOML requires.
+fn push_table(root: &JsonValue, object: &JsonValue, path: Option<&str>, lines: &mut Vec<String>) {
+  let properties = object["properties"].as_object().into_iter().flatten().collect::<Vec<(&String, &JsonValue)>>();
+
+  for (key, property) in properties.iter().filter(|(_, p)| !is_table(root, p)) {
+    if path.is_none() && !lines.is_empty() {
+      lines.push("".to_string());
+    }
+
+    push_description(root, property, lines);
+
+    let line = format!("{} = {}", key, value_of(resolve(root, property)));
+
+    match path.is_none() && REQUIRED.contains(&key.as_str()) {
+      true => lines.push(line),
+      false => lines.push(format!("# {}", line)),
+    }
+  }
+
+  for (key, property) in properties.iter().filter(|(_, p)| is_table(root, p)) {
+    let name = path.map(|p| format!("{}.{}", p, key)).unwrap_or(key.to_string());
+    let schema = resolve(root, property);
+    let items = resolve(root, &schema["items"]);
+    let values = resolve(root, &schema["additionalProperties"]);
+    let (header, table) = if items["properties"].is_object() {
+      (format!("[[{}]]", name), items)
+    } else if values["properties"].is_object() {
+      (format!("[{}.\"<name>\"]", name), values)
+    } else {
+      (format!("[{}]", name), schema)
+    };
+
+    // The table that has only the tables is implied by them.
+    if table["properties"].as_object().is_some_and(|p| p.values().all(|p| is_table(root, p))) {
+      push_table(root, table, Some(&name), lines);
+      continue;
+    }
+
+    lines.push("".to_string());
+    push_description(root, property, lines);
+    lines.push(format!("# {}", header));
+
+    match table["properties"].is_object() {
+      true => push_table(root, table, Some(header.trim_matches(|c| c == '[' || c == ']')), lines),
+      false => lines.push(format!("# \"<name>\" = {}", value_of(values))),
+    }
+  }
+}
+
+/// Whether the value is written as the table. The structs, the maps and the lists of the structs are.
+fn is_table(root: &JsonValue, property: &JsonValue) -> bool {
+  let schema = resolve(root, property);
 
-# URL for the repository
-repository = ""
-"#
-  .to_string();
+  return schema["properties"].is_object() || schema["additionalProperties"].is_object() || resolve(root, &schema["items"])["properties"].is_object();
 }
 
-fn file_path(name: &str) -> Box<PathBuf> {
+/// Doc comment of the field, or the one of the type if the field has no comment.
+fn push_description(root: &JsonValue, property: &JsonValue, lines: &mut Vec<String>) {
+  let description = property["description"].as_str().or(resolve(root, property)["description"].as_str());
+
+  for line in description.into_iter().flat_map(|d| d.lines()) {
+    lines.push(format!("# {}", line));
+  }
+}
+
+/// Schema that the reference or the optional value points to.
+fn resolve<'a>(root: &'a JsonValue, schema: &'a JsonValue) -> &'a JsonValue {
+  if let Some(name) = schema["$ref"].as_str().and_then(|r| r.strip_prefix("#/definitions/")) {
+    return resolve(root, &root["definitions"][name]);
+  }
+
+  if let Some(s) = schema["anyOf"].as_array().and_then(|a| a.iter().find(|s| s["type"] != "null")) {
+    return resolve(root, s);
+  }
+
+  return schema;
+}
+
+/// Default of the schema in TOML. The empty value of the type is used if it has no default.
+fn value_of(schema: &JsonValue) -> String {
+  if !schema["default"].is_null() {
+    return toml::Value::try_from(&schema["default"]).unwrap().to_string();
+  }
+
+  let types = match &schema["type"] {
+    JsonValue::Array(list) => list.clone(),
+    t => vec![t.clone()],
+  };
+  let enums = schema["enum"].as_array().or(schema["oneOf"][0]["enum"].as_array());
+
+  return match types.iter().find_map(|t| t.as_str().filter(|t| *t != "null")) {
+    _ if enums.is_some() => toml::Value::try_from(&enums.unwrap()[0]).unwrap().to_string(),
+    Some("boolean") => "false".to_string(),
+    Some("integer") => (schema["minimum"].as_f64().unwrap_or_default() as i64).to_string(),
+    Some("number") => toml::Value::Float(schema["minimum"].as_f64().unwrap_or_default()).to_string(),
+    Some("array") => "[]".to_string(),
+    Some("object") => "{}".to_string(),
+    _ => "\"\"".to_string(),
+  };
+}
+
+pub fn file_path(name: &str) -> Box<PathBuf> {
   let c = config::current();
 
   return Box::new(c.app_root().join(format!("{}.toml", c.resolve(name))));
 }
 
+/// Secrets written decrypted for the containers. They're blanked when it's dropped, including the unwinding of the panic.
+struct DecryptedSecrets<'a> {
+  app: &'a Application,
+}
+
+impl Drop for DecryptedSecrets<'_> {
+  fn drop(&mut self) {
+    self.app.write_secrets(false);
+  }
+}
+
 impl Application {
   pub fn root(&self) -> Box<PathBuf> {
-    return Box::new(config::current().root().join(&self.values.path.as_ref().unwrap()));
+    return Box::new(config::current().root().join(self.values.path.as_ref().unwrap()));
   }
 
   pub fn dist_root(&self) -> Box<PathBuf> {
     return Box::new(config::current().root().join(".dist").join(&self.name));
   }
 
+  /// The main compose file. It's the first one of `compose.files`.
+  pub fn compose_file_path(&self) -> Box<PathBuf> {
+    return self.compose_file_paths().remove(0);
+  }
+
+  pub fn compose_file_paths(&self) -> Vec<Box<PathBuf>> {
+    let files = self.values.compose.as_ref().and_then(|c| c.files.clone()).or_else(default_compose_files).unwrap();
+
+    return files.iter().map(|f| Box::new(self.root().join(f))).collect();
+  }
+
+  pub fn depends_on(&self) -> Vec<String> {
+    return self.values.depends_on.clone().unwrap_or_default();
+  }
+
+  pub fn is_shared(&self) -> bool {
+    return self.values.shared.unwrap_or(false);
+  }
+
+  pub fn uses(&self) -> Vec<String> {
+    return self.values.uses.clone().unwrap_or_default();
+  }
+
+  /// Applications allowed to connect to the services. None means all of them.
+  pub fn allow_from(&self) -> Option<Vec<String>> {
+    return self.values.allow_from.clone();
+  }
+
+  /// Whether any container of the application is running.
+  pub fn is_running(&self) -> bool {
+    return engine::current().is_running(&self.name);
+  }
+
+  /// Services that aren't started.
+  pub fn skipped_services(&self) -> Vec<String> {
+    return self.values.compose.as_ref().and_then(|c| c.skip.clone()).unwrap_or_default();
+  }
+
+  /// Ports published to the host by the service name.
+  pub fn published_ports(&self) -> BTreeMap<String, Vec<String>> {
+    return self.values.ports.clone().unwrap_or_default();
+  }
+
+  /// Limits of the service. The ones of the service override the defaults one by one.
+  pub fn resources_of(&self, service: &str) -> LimitsValues {
+    let resources = self.values.resources.clone().unwrap_or_default();
+    let defaults = resources.defaults;
+
+    return match resources.services.and_then(|mut s| s.remove(service)) {
+      Some(l) => LimitsValues {
+        cpus: l.cpus.or(defaults.cpus),
+        mem_limit: l.mem_limit.or(defaults.mem_limit),
+        pids_limit: l.pids_limit.or(defaults.pids_limit),
+      },
+      None => defaults,
+    };
+  }
+
+  /// Limits of the services that are started.
+  pub fn limits(&self) -> BTreeMap<String, LimitsValues> {
+    let skipped = self.skipped_services();
+
+    return self
+      .compose_file_paths()
+      .into_iter()
+      .flat_map(|f| docker_compose::load(f).services.unwrap_or_default())
+      .map(|(name, s)| s.container_name.unwrap_or(name))
+      .filter(|name| !skipped.contains(name))
+      .map(|name| {
+        let limits = self.resources_of(&name);
+
+        (name, limits)
+      })
+      .collect();
+  }
+
+  /// Whether the repository is synchronized into the VM.
+  pub fn is_synced(&self) -> bool {
+    return self.values.sync.as_ref().and_then(|s| s.enabled).or_else(default_sync_enabled).unwrap();
+  }
+
+  pub fn sync_mode(&self) -> Option<String> {
+    return self.values.sync.as_ref().and_then(|s| s.mode.clone());
+  }
+
+  pub fn sync_ignore(&self) -> Vec<String> {
+    return self.values.sync.as_ref().and_then(|s| s.ignore.clone()).unwrap_or_default();
+  }
+
   pub fn name(&self) -> &str {
     return self.name.as_str();
   }
 
+  /// The primary domain. It's the one of the first alias if the application has aliases.
   pub fn domain(&self) -> String {
-    let name = config::current().get_alias(self.name.as_str()).unwrap_or(self.name.clone());
+    return self.domains().remove(0);
+  }
+
+  /// Check the value of the record, and that the absolute name is in the domains of the aliases and the name.
+  /// The domain of the workspace isn't included even for the shared applications not to take over the names of the others.
+  pub fn check_record(&self, record: &Record) -> Result<(), String> {
+    record.check()?;
+
+    let c = config::current();
+    let domains = self.domains().into_iter().filter(|d| d != c.dns().domain()).collect::<Vec<String>>();
+
+    if !record.is_in(&domains) {
+      return Err(format!("{} is out of the domains {}", record.name, domains.join(", ")));
+    }
+
+    return Ok(());
+  }
+
+  /// Domains of the aliases and the name. The services are published under all of them.
+  pub fn domains(&self) -> Vec<String> {
+    let c = config::current();
+    let mut names = c.aliases_of(&self.name);
+
+    names.push(self.name.clone());
+
+    let mut domains = names.iter().map(|n| format!("{}.{}", n, c.dns().domain())).collect::<Vec<String>>();
+
+    if self.is_shared() {
+      domains.push(c.dns().domain().to_string());
+    }
+
+    return domains;
+  }
+
+  pub fn records(&self) -> Vec<Record> {
+    return self.values.dns.as_ref().and_then(|d| d.records.clone()).unwrap_or_default();
+  }
+
+  pub fn aliases(&self) -> BTreeMap<String, Vec<String>> {
+    return self.values.dns.as_ref().and_then(|d| d.aliases.clone()).unwrap_or_default();
+  }
+
+  /// Env file of the layers except for the secrets.
+  pub fn env_file_path(&self) -> Box<PathBuf> {
+    return Box::new(self.dist_root().join("hills.env"));
+  }
 
-    return format!("{}.{}", name.as_str(), config::current().dns().domain());
+  /// Env file of the decrypted secrets. The values are written only while `up` runs, and it's empty otherwise.
+  pub fn secret_file_path(&self) -> Box<PathBuf> {
+    return Box::new(self.dist_root().join("secret.env"));
   }
 
-  pub fn update(&self, force: bool) {
+  /// File of the layer. The template layer is in the application TOML.
+  pub fn env_file_of(&self, layer: Layer) -> Option<Box<PathBuf>> {
+    let dir = config::current().root().join("env");
+
+    return match layer {
+      Layer::Repository => Some(Box::new(self.root().join(".env"))),
+      Layer::Template => None,
+      Layer::Workspace => Some(Box::new(dir.join(format!("{}.env", self.name)))),
+      Layer::Local => Some(Box::new(dir.join(format!("{}.local.env", self.name)))),
+      Layer::Secret => Some(Box::new(dir.join(format!("{}.secret.env", self.name)))),
+    };
+  }
+
+  /// Values of the layer as they're written. The secrets are encrypted.
+  pub fn raw_env_of(&self, layer: Layer) -> BTreeMap<String, String> {
+    return match self.env_file_of(layer) {
+      Some(file) => dotenv::load(&file),
+      None => self.values.env.clone().unwrap_or_default(),
+    };
+  }
+
+  /// Values of the layer. The templates are rendered and the secrets are decrypted.
+  pub fn env_of(&self, layer: Layer) -> BTreeMap<String, String> {
+    let raw = self.raw_env_of(layer);
+
+    if raw.is_empty() {
+      return raw;
+    }
+
+    return match layer {
+      Layer::Template => self.render_templates(raw),
+      Layer::Secret => {
+        let cipher = secret::load();
+
+        raw
+          .into_iter()
+          .map(|(k, v)| {
+            let value = cipher.decrypt(&v).unwrap_or_else(|e| panic!("Failed to decrypt {} of {} : {}", k, self.name, e));
+
+            (k, value)
+          })
+          .collect()
+      }
+      _ => raw,
+    };
+  }
+
+  /// Effective values with the layer where they come from.
+  pub fn effective_env(&self) -> BTreeMap<String, (Layer, String)> {
+    let mut values: BTreeMap<String, (Layer, String)> = BTreeMap::new();
+
+    for layer in dotenv::LAYERS {
+      for (k, v) in self.env_of(layer) {
+        values.insert(k, (layer, v));
+      }
+    }
+
+    return values;
+  }
+
+  pub fn set_env(&self, layer: Layer, key: &str, value: &str) {
+    let mut values = self.raw_env_of(layer);
+    let value = if layer == Layer::Secret { secret::load().encrypt(value) } else { value.to_string() };
+
+    values.insert(key.to_string(), value);
+    self.save_env(layer, values);
+  }
+
+  /// Remove the key from the layer and return whether it existed.
+  pub fn unset_env(&self, layer: Layer, key: &str) -> bool {
+    let mut values = self.raw_env_of(layer);
+
+    if values.remove(key).is_none() {
+      return false;
+    }
+
+    self.save_env(layer, values);
+
+    return true;
+  }
+
+  fn save_env(&self, layer: Layer, values: BTreeMap<String, String>) {
+    let file = match layer {
+      Layer::Repository | Layer::Template => panic!("The {:?} layer is read only. Edit it in the repository or the application TOML", layer),
+      _ => self.env_file_of(layer).unwrap(),
+    };
+    let dir = file.parent().unwrap();
+
+    if !dir.exists() {
+      fs::create_dir_all(dir).expect(&format!("Failed to create {:?}", dir));
+    }
+
+    // The secrets are encrypted with the key of the user. The others can't decrypt them.
+    let ignore = dir.join(".gitignore");
+    let patterns = if ignore.exists() {
+      fs::read_to_string(&ignore).expect(&format!("Failed to read {:?}", ignore))
+    } else {
+      "".to_string()
+    };
+    let missing = ENV_IGNORES.iter().filter(|p| !patterns.lines().any(|l| l.trim() == **p)).map(|p| format!("{}\n", p)).collect::<String>();
+
+    if !missing.is_empty() {
+      config::create_file(Box::new(ignore), format!("{}{}", patterns, missing));
+    }
+
+    config::create_file(file, dotenv::render(&values));
+  }
+
+  /// Render `[env]` of the application. The referenced services have to be assigned the addresses.
+  fn render_templates(&self, templates: BTreeMap<String, String>) -> BTreeMap<String, String> {
+    let leases = config::current().dns().leases();
+    let lookup = |r: &Reference| -> Result<String, String> {
+      let name = config::current().try_resolve(&r.app).ok_or(format!("Not found application {}", r.app))?;
+      let app = find_by(&name);
+      let lease = leases
+        .get(&name)
+        .and_then(|services| services.get(&r.service))
+        .ok_or(format!("Not found the service {} of {}. Run `hills update {}` first", r.service, name, name))?;
+
+      return match r.attribute {
+        Attribute::Fqdn => Ok(format!("{}.{}", r.service, app.domain())),
+        Attribute::Ip => lease.addrs().first().map(|a| a.to_string()).ok_or(format!("Not found the address of {}", r.service)),
+        Attribute::Ip6 => lease.ipv6.map(|a| a.to_string()).ok_or(format!("Not found the IPv6 address of {}", r.service)),
+        Attribute::Port => app.port_of(&r.service).ok_or(format!("Not found the port of {}", r.service)),
+      };
+    };
+
+    return templates
+      .into_iter()
+      .map(|(key, template)| match environment::render(&template, &lookup) {
+        Ok(value) => (key, value),
+        Err(e) => panic!("Invalid application {} : env.{} : {}", self.name, key, e),
+      })
+      .collect();
+  }
+
+  /// The first port that the service exposes in the container.
+  pub fn port_of(&self, service: &str) -> Option<String> {
+    let services = self.compose_file_paths().into_iter().flat_map(|f| docker_compose::load(f).services.unwrap_or_default());
+
+    for (name, s) in services {
+      if name != service && s.container_name.as_deref() != Some(service) {
+        continue;
+      }
+
+      let entries = s.ports.unwrap_or_default().into_iter().chain(s.expose.unwrap_or_default());
+
+      return entries.map(|e| environment::container_port_of(&e)).next();
+    }
+
+    return None;
+  }
+
+  /// Regenerate the files under `.dist/<app>` and return whether they're regenerated.
+  pub fn update(&self, force: bool) -> bool {
     let dir = self.dist_root();
-    let mut synchronizer = synchronizer::new(&self);
+    let mut synchronizer = synchronizer::new(self);
 
     if !dir.exists() {
       fs::create_dir_all(*dir.clone()).expect(&format!("Failed to create {:?}", dir));
     }
 
     if synchronizer.is_up_to_date() && !force {
-      return;
+      return false;
     }
 
     self.clear_dist();
     synchronizer.perform();
+    self.run_hooks("post_update", self.values.hooks.as_ref().and_then(|h| h.post_update.clone()));
+
+    return true;
+  }
+
+  pub fn up(&self) {
+    // The shared applications are started separately not to restart them with every consumer.
+    for name in self.uses() {
+      let used = find_by(&config::current().resolve(&name));
+
+      if !used.is_shared() {
+        panic!("Invalid application {} : uses : {} is not shared", self.name, name);
+      }
+
+      if !used.is_running() {
+        panic!("{} isn't running. Run `hills up {}` first", used.name, used.name);
+      }
+    }
+
+    self.run_hooks("pre_up", self.values.hooks.as_ref().and_then(|h| h.pre_up.clone()));
+
+    let runner = self.make_runner();
+
+    // The containers read the secrets when they're created. They're removed from the disk right after that even if it fails.
+    self.write_secrets(true);
+
+    let decrypted = DecryptedSecrets { app: self };
+
+    runner.up();
+    drop(decrypted);
+    self.run_hooks("post_up", self.values.hooks.as_ref().and_then(|h| h.post_up.clone()));
   }
 
   pub fn print(&self) {
     self.make_runner().ps();
   }
 
+  /// What `remove` does. They're shown to confirm before removing.
+  pub fn removal_steps(&self, keep_volumes: bool, purge_repo: bool) -> Vec<String> {
+    let mut steps: Vec<String> = vec![];
+
+    if !self.docker_compose_paths().is_empty() {
+      steps.push(if keep_volumes { "Stop the containers".to_string() } else { "Stop the containers and remove the volumes".to_string() });
+    }
+
+    steps.push("Release the subnets and the leases, and remove the DNS config".to_string());
+
+    let aliases = config::current().aliases_of(&self.name);
+
+    if !aliases.is_empty() {
+      steps.push(format!("Remove the aliases {}", aliases.join(", ")));
+    }
+
+    steps.push(format!("Terminate the sync session {}", self.name));
+    steps.push(format!("Delete {:?}", self.dist_root()));
+    steps.push(format!("Delete {:?}", file_path(&self.name)));
+
+    if purge_repo {
+      let repo = self.repository_dir();
+
+      if config::current().root().canonicalize().is_ok_and(|r| repo.starts_with(r)) {
+        steps.push(format!("Delete the repository {:?}", repo));
+      } else {
+        steps.push(format!("Delete the repository {:?} outside of the workspace", repo));
+      }
+    }
+
+    return steps;
+  }
+
+  /// Directory of the repository without `..` and the symlinks to compare it with the other directories.
+  fn repository_dir(&self) -> Box<PathBuf> {
+    let root = self.root();
+
+    return Box::new(root.canonicalize().unwrap_or_else(|_| root.absolutize().unwrap().to_path_buf()));
+  }
+
+  /// Check that the repository can be deleted without the root and the repositories of the other applications.
+  pub fn check_purge(&self) -> Result<(), String> {
+    let c = config::current();
+    let repo = self.repository_dir();
+
+    if c.root().canonicalize().is_ok_and(|r| r.starts_with(repo.as_ref())) {
+      return Err(format!("{:?} contains the root. It can't be deleted", repo));
+    }
+
+    for other in c.application_names().into_iter().filter(|n| *n != self.name) {
+      if load(&other).is_ok_and(|a| a.repository_dir().starts_with(repo.as_ref())) {
+        return Err(format!("{:?} contains the repository of {}. It can't be deleted", repo, other));
+      }
+    }
+
+    return Ok(());
+  }
+
+  /// Remove the application and everything generated for it. The env files under the root are kept.
+  pub fn remove(&self, keep_volumes: bool, purge_repo: bool) {
+    let c = config::current();
+    let repo = self.repository_dir();
+
+    if purge_repo {
+      self.check_purge().unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    // The references are collected before the aliases and the file are removed because they can't be resolved after that.
+    let refers = |names: Vec<String>| names.iter().any(|n| c.try_resolve(n).as_ref() == Some(&self.name));
+    let mut warnings = vec![];
+
+    for other in c.application_names().into_iter().filter(|n| *n != self.name) {
+      if let Ok(a) = load(&other) {
+        if refers(a.depends_on()) {
+          warnings.push(format!("{} still depends on {}", other, self.name));
+        }
+
+        if refers(a.uses()) {
+          warnings.push(format!("{} still uses {}", other, self.name));
+        }
+
+        if refers(a.allow_from().unwrap_or_default()) {
+          warnings.push(format!("{} still allows {} in allow_from", other, self.name));
+        }
+      }
+    }
+
+    if !self.docker_compose_paths().is_empty() {
+      self.make_runner().down(!keep_volumes);
+    }
+
+    c.dns().release(&self.name);
+    config::remove_aliases_of(&self.name);
+
+    for dir in [self.dist_root()].into_iter().chain(purge_repo.then_some(repo)) {
+      if dir.exists() {
+        fs::remove_dir_all(dir.as_ref()).expect(&format!("Failed to remove {:?}", dir));
+        println!("Deleted {:?}", dir);
+      }
+    }
+
+    let f = file_path(&self.name);
+
+    fs::remove_file(f.as_ref()).expect(&format!("Failed to remove {:?}", f));
+    println!("Deleted {:?}", f);
+
+    let mutagen = c.mutagen();
+
+    mutagen.terminate(&self.name);
+    mutagen.update();
+
+    for w in warnings {
+      eprintln!("{}", w);
+    }
+  }
+
+  /// Problems that the rules of the schema can't find. e.g. the references to the other applications or the services.
+  pub fn check(&self) -> Vec<String> {
+    let mut errors: Vec<String> = vec![];
+
+    for name in self.depends_on() {
+      if name == self.name || config::current().try_resolve(&name).is_none() {
+        errors.push(format!("depends_on : Not found application {}", name));
+      }
+    }
+
+    for name in self.uses() {
+      match config::current().try_resolve(&name).filter(|n| *n != self.name).map(|n| load(&n)) {
+        None => errors.push(format!("uses : Not found application {}", name)),
+        Some(Ok(app)) if !app.is_shared() => errors.push(format!("uses : {} is not shared", name)),
+        Some(Ok(app)) if app.allow_from().is_some_and(|a| !a.iter().any(|n| config::current().try_resolve(n).as_ref() == Some(&self.name))) => errors.push(format!("uses : {} doesn't allow {} in allow_from", name, self.name)),
+        _ => {}
+      }
+    }
+
+    for name in self.values.allow_from.iter().flatten() {
+      if config::current().try_resolve(name).is_none() {
+        errors.push(format!("allow_from : Not found application {}", name));
+      }
+    }
+
+    let mut services: Vec<String> = vec![];
+    // The aliases can be given by the service name as well as container_name.
+    let mut names: Vec<String> = vec![];
+
+    for file in self.compose_file_paths() {
+      if !file.exists() {
+        errors.push(format!("compose.files : Not found {:?}", file));
+        continue;
+      }
+
+      for (name, s) in docker_compose::load(file).services.unwrap_or_default() {
+        names.push(name.clone());
+        services.push(s.container_name.unwrap_or(name));
+      }
+    }
+
+    if !services.is_empty() {
+      let referenced = self
+        .skipped_services()
+        .into_iter()
+        .map(|s| ("compose.skip", s))
+        .chain(self.published_ports().into_keys().map(|s| ("ports", s)))
+        .chain(self.aliases().into_keys().map(|s| ("dns.aliases", s)))
+        .chain(self.values.resources.iter().flat_map(|r| r.services.clone().unwrap_or_default().into_keys()).map(|s| ("resources.services", s)));
+
+      for (key, service) in referenced {
+        let found = services.contains(&service) || (key == "dns.aliases" && names.contains(&service));
+
+        if !found {
+          errors.push(format!("{} : Not found the service {}", key, service));
+        }
+      }
+    }
+
+    for (key, template) in self.raw_env_of(Layer::Template) {
+      if let Err(e) = environment::references(&template) {
+        errors.push(format!("env.{} : {}", key, e));
+      }
+    }
+
+    for r in self.records() {
+      if let Err(e) = self.check_record(&r) {
+        errors.push(format!("dns.records : {}", e));
+      }
+    }
+
+    return errors;
+  }
+
+  fn run_hooks(&self, name: &str, commands: Option<Vec<String>>) {
+    for command in commands.unwrap_or_default() {
+      let ok = process::Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .current_dir(self.root().as_ref())
+        .status()
+        .expect(&format!("Failed to execute {}", command))
+        .success();
+
+      if !ok {
+        panic!("Failed to run the {} hook of {} : {}", name, self.name, command);
+      }
+    }
+  }
+
   fn clear_dist(&self) {
     self.docker_compose_paths().iter().for_each(|p| {
       fs::remove_file(*p.clone()).expect(&format!("Failed to remove {:?}", p));
     });
+
+    for file in [self.env_file_path(), self.secret_file_path()] {
+      if file.exists() {
+        fs::remove_file(*file.clone()).expect(&format!("Failed to remove {:?}", file));
+      }
+    }
+  }
+
+  /// Write the env file of the secrets that's referenced by the override file. It's empty unless `decrypted` so that compose can load it without the secrets.
+  fn write_secrets(&self, decrypted: bool) {
+    if self.raw_env_of(Layer::Secret).is_empty() {
+      return;
+    }
+
+    let file = self.secret_file_path();
+    let content = if decrypted { dotenv::render(&self.env_of(Layer::Secret)) } else { "".to_string() };
+    let mut f = OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(file.as_ref()).expect(&format!("Failed to write {:?}", file));
+
+    // The mode is applied only when the file is created.
+    f.set_permissions(fs::Permissions::from_mode(0o600)).expect(&format!("Failed to change the mode of {:?}", file));
+    write!(f, "{}", content).expect(&format!("Failed to write {:?}", file));
   }
 
   fn make_runner(&self) -> Box<runner::Runner> {
+    self.write_secrets(false);
+
     return Box::new(runner::new(self, self.docker_compose_paths()));
   }
 
@@ -118,12 +1242,49 @@ impl Application {
       fs::read_dir(*dir.clone()).expect(&format!("Failed to read {:?}", dir)).for_each(|f| {
         let path = f.unwrap().path();
 
-        if path.is_file() {
+        if path.is_file() && path.extension().is_some_and(|e| e == "yml") {
           list.push(Box::new(path));
         }
       });
     }
 
+    // The override file has to be the last.
+    list.sort();
+
     return list;
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn template_is_generated_from_the_schema() {
+    let t = template();
+    let v: Values = toml::from_str(&t).unwrap();
+
+    assert_eq!(v.version, Some(VERSION));
+    assert!(v.compose.is_none());
+    assert!(t.contains("# Compose files relative to the repository.\n# files = [\"docker-compose.yml\"]\n"));
+    assert!(t.contains("# [sync]\n# Whether the repository is synchronized into the VM by mutagen.\n# enabled = true\n"));
+    assert!(t.contains("# [resources.services.\"<name>\"]\n"));
+
+    let uncommented = t.lines().map(|l| l.strip_prefix("# ").unwrap_or(l)).filter(|l| l.contains(" = ") || l.starts_with('[')).collect::<Vec<&str>>().join("\n");
+    let v: Values = toml::from_str(&uncommented).unwrap();
+
+    assert!(v.dns.is_some_and(|d| d.records.is_some() && d.aliases.is_some()));
+    assert!(v.resources.is_some_and(|r| r.services.is_some()));
+  }
+
+  #[test]
+  fn schema_has_the_doc_comments_and_the_constraints() {
+    let schema = serde_json::to_value(schema()).unwrap();
+    let resources = &schema["definitions"]["ResourcesValues"]["properties"];
+
+    assert_eq!(schema["required"], serde_json::json!(["path"]));
+    assert_eq!(schema["properties"]["path"]["minLength"], 1);
+    assert_eq!(resources["mem_limit"]["description"], "Memory size. (e.g. \"512m\", \"1g\")");
+    assert_eq!(resources["mem_limit"]["pattern"], config::SIZE.as_str());
+  }
+}
@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Layers of the environment variables in the order of the precedence from low to high.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Layer {
+  /// `.env` in the repository.
+  Repository,
+  /// `[env]` in the application TOML.
+  Template,
+  /// `env/<app>.env` under the root.
+  Workspace,
+  /// `env/<app>.local.env` under the root. It's ignored by git.
+  Local,
+  /// `env/<app>.secret.env` under the root. The values are encrypted.
+  Secret,
+}
+
+pub const LAYERS: [Layer; 5] = [Layer::Repository, Layer::Template, Layer::Workspace, Layer::Local, Layer::Secret];
+
+/// Parse `KEY=VALUE` lines. Comments, blank lines and `export` are allowed and the quotes are removed.
+pub fn parse(s: &str) -> BTreeMap<String, String> {
+  let mut values: BTreeMap<String, String> = BTreeMap::new();
+
+  for line in s.lines() {
+    let line = line.trim();
+
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    let line = line.strip_prefix("export ").unwrap_or(line);
+
+    if let Some((key, value)) = line.split_once('=') {
+      let value = value.trim();
+      let unquoted = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(v) => unescape(v),
+        None => value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')).unwrap_or(value).to_string(),
+      };
+
+      values.insert(key.trim().to_string(), unquoted);
+    }
+  }
+
+  return values;
+}
+
+/// Render `KEY=VALUE` lines. The values that have comments, spaces, quotes or newlines are quoted.
+pub fn render(values: &BTreeMap<String, String>) -> String {
+  return values.iter().map(|(k, v)| format!("{}={}\n", k, quote(v))).collect::<Vec<String>>().join("");
+}
+
+fn quote(value: &str) -> String {
+  if !value.chars().any(|c| c == '#' || c == '"' || c == '\'' || c == '\\' || c.is_whitespace()) {
+    return value.to_string();
+  }
+
+  if !value.contains('\'') && !value.contains('\n') {
+    return format!("'{}'", value);
+  }
+
+  return format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"));
+}
+
+/// Unescape the value in the double quotes.
+fn unescape(value: &str) -> String {
+  let mut unescaped = "".to_string();
+  let mut chars = value.chars();
+
+  while let Some(c) = chars.next() {
+    match (c, chars.clone().next()) {
+      ('\\', Some('n')) => unescaped.push('\n'),
+      ('\\', Some(next @ ('"' | '\\'))) => unescaped.push(next),
+      _ => {
+        unescaped.push(c);
+        continue;
+      }
+    }
+
+    chars.next();
+  }
+
+  return unescaped;
+}
+
+/// Values in the file. It's empty if the file doesn't exist.
+pub fn load(file: &Path) -> BTreeMap<String, String> {
+  if !file.exists() {
+    return BTreeMap::new();
+  }
+
+  return parse(&fs::read_to_string(file).expect(&format!("Failed to read {:?}", file)));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn quotes_and_comments_are_handled() {
+    let values = parse("# comment\nexport A=1\nB=\"two words\"\n\nC='x=y'\n");
+
+    assert_eq!(values, BTreeMap::from([("A".to_string(), "1".to_string()), ("B".to_string(), "two words".to_string()), ("C".to_string(), "x=y".to_string())]));
+    assert_eq!(render(&values), "A=1\nB='two words'\nC=x=y\n");
+  }
+
+  #[test]
+  fn rendered_values_are_parsed_back() {
+    let values = BTreeMap::from([
+      ("COLOR".to_string(), "#fff".to_string()),
+      ("KEY".to_string(), "-----BEGIN KEY-----\nabc\n-----END KEY-----".to_string()),
+      ("QUOTED".to_string(), "it's \"ok\" \\o/".to_string()),
+    ]);
+    let rendered = render(&values);
+
+    assert_eq!(rendered, "COLOR='#fff'\nKEY=\"-----BEGIN KEY-----\\nabc\\n-----END KEY-----\"\nQUOTED=\"it's \\\"ok\\\" \\\\o/\"\n");
+    assert_eq!(parse(&rendered), values);
+  }
+}
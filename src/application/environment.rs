@@ -0,0 +1,100 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static EXPRESSION: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{\s*(.*?)\s*\}\}").unwrap());
+static REFERENCE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^app\("([^"]+)"\)\.service\("([^"]+)"\)\.(fqdn|ip|ip6|port)$"#).unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Attribute {
+  Fqdn,
+  /// IPv4 address, or IPv6 address if the application has no IPv4 address.
+  Ip,
+  Ip6,
+  /// The first port that the service exposes in the container.
+  Port,
+}
+
+/// Service of the other application referenced by `{{ app("<app>").service("<service>").<attribute> }}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+  pub app: String,
+  pub service: String,
+  pub attribute: Attribute,
+}
+
+fn parse(expression: &str) -> Result<Reference, String> {
+  let caps = REFERENCE.captures(expression).ok_or(format!("Unsupported expression `{}`", expression))?;
+  let attribute = match &caps[3] {
+    "fqdn" => Attribute::Fqdn,
+    "ip" => Attribute::Ip,
+    "ip6" => Attribute::Ip6,
+    _ => Attribute::Port,
+  };
+
+  return Ok(Reference {
+    app: caps[1].to_string(),
+    service: caps[2].to_string(),
+    attribute: attribute,
+  });
+}
+
+pub fn references(template: &str) -> Result<Vec<Reference>, String> {
+  return EXPRESSION.captures_iter(template).map(|c| parse(&c[1])).collect();
+}
+
+/// Replace the expressions in the template with the values looked up.
+pub fn render(template: &str, lookup: &dyn Fn(&Reference) -> Result<String, String>) -> Result<String, String> {
+  let mut rendered = "".to_string();
+  let mut last = 0;
+
+  for caps in EXPRESSION.captures_iter(template) {
+    let m = caps.get(0).unwrap();
+
+    rendered.push_str(&template[last..m.start()]);
+    rendered.push_str(&lookup(&parse(&caps[1])?)?);
+    last = m.end();
+  }
+
+  rendered.push_str(&template[last..]);
+
+  return Ok(rendered);
+}
+
+/// Port in the container of the entry of `ports` or `expose`. (e.g. "127.0.0.1:8080:80/tcp" to "80")
+pub fn container_port_of(entry: &str) -> String {
+  let port = entry.rsplit(':').next().unwrap();
+  let port = port.split('/').next().unwrap();
+
+  return port.split('-').next().unwrap().to_string();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn expressions_are_replaced() {
+    let lookup = |r: &Reference| -> Result<String, String> {
+      return match r.attribute {
+        Attribute::Fqdn => Ok(format!("{}.{}.local", r.service, r.app)),
+        Attribute::Port => Ok("5432".to_string()),
+        _ => Err(format!("Not found {}", r.service)),
+      };
+    };
+
+    assert_eq!(
+      render(r#"postgres://{{ app("shared").service("postgres").fqdn }}:{{app("shared").service("postgres").port}}/db"#, &lookup),
+      Ok("postgres://postgres.shared.local:5432/db".to_string())
+    );
+    assert_eq!(render("plain", &lookup), Ok("plain".to_string()));
+    assert!(render(r#"{{ app("shared").service("redis").ip }}"#, &lookup).is_err());
+    assert!(references(r#"{{ app("shared").domain }}"#).is_err());
+  }
+
+  #[test]
+  fn container_port_is_taken_from_the_entry() {
+    assert_eq!(container_port_of("127.0.0.1:8080:80/tcp"), "80");
+    assert_eq!(container_port_of("5432"), "5432");
+    assert_eq!(container_port_of("3000-3005"), "3000");
+  }
+}
@@ -0,0 +1,187 @@
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process;
+
+/// Main compose files in the order of the precedence of docker compose.
+const COMPOSE_FILES: [&str; 4] = ["compose.yaml", "compose.yml", "docker-compose.yaml", "docker-compose.yml"];
+
+/// Override files that docker compose merges implicitly.
+const OVERRIDE_FILES: [&str; 4] = ["compose.override.yaml", "compose.override.yml", "docker-compose.override.yaml", "docker-compose.override.yml"];
+
+/// Directories that aren't scanned for the repositories.
+const SKIPPED_DIRS: [&str; 3] = ["node_modules", "vendor", "target"];
+
+/// What's found in the checkout.
+pub struct Detected {
+  /// Compose files relative to the repository.
+  pub files: Vec<String>,
+  /// Published and exposed ports by the service name.
+  pub services: BTreeMap<String, Vec<String>>,
+  pub repository: Option<String>,
+  /// Features of compose that hills can't handle yet.
+  pub warnings: Vec<String>,
+}
+
+pub fn detect(dir: &Path) -> Result<Detected, String> {
+  let main = COMPOSE_FILES.iter().find(|f| dir.join(f).exists()).ok_or(format!("Not found compose files in {:?}", dir))?;
+  let files = [*main]
+    .into_iter()
+    .chain(OVERRIDE_FILES.iter().copied().filter(|f| dir.join(f).exists()).take(1))
+    .map(|f| f.to_string())
+    .collect::<Vec<String>>();
+  let mut services: BTreeMap<String, Vec<String>> = BTreeMap::new();
+  let mut warnings: Vec<String> = vec![];
+
+  for file in files.iter() {
+    let path = dir.join(file);
+    let s = fs::read_to_string(&path).map_err(|e| format!("Failed to read {:?} : {}", path, e))?;
+    let yaml: Value = serde_yaml::from_str(&s).map_err(|e| format!("Failed to load {:?} : {}", path, e))?;
+
+    warnings.extend(warnings_of(&yaml).into_iter().map(|w| format!("{} : {}", file, w)));
+
+    for (name, service) in yaml.get("services").and_then(|s| s.as_mapping()).into_iter().flatten() {
+      let ports = services.entry(name.as_str().unwrap_or_default().to_string()).or_default();
+
+      for key in ["ports", "expose"] {
+        ports.extend(service.get(key).and_then(|p| p.as_sequence()).into_iter().flatten().filter_map(port_of));
+      }
+    }
+  }
+
+  return Ok(Detected {
+    files: files,
+    services: services,
+    repository: repository_of(dir),
+    warnings: warnings,
+  });
+}
+
+fn port_of(value: &Value) -> Option<String> {
+  return match value {
+    Value::String(s) => Some(s.clone()),
+    Value::Number(n) => Some(n.to_string()),
+    Value::Mapping(m) => m.get("target").map(|t| serde_yaml::to_string(t).unwrap().trim().to_string()),
+    _ => None,
+  };
+}
+
+/// Features that aren't rewritten by the synchronizer.
+fn warnings_of(yaml: &Value) -> Vec<String> {
+  let mut warnings: Vec<String> = vec![];
+
+  for key in ["include", "networks"] {
+    if yaml.get(key).is_some() {
+      warnings.push(format!("Top level `{}` is not supported", key));
+    }
+  }
+
+  for (name, service) in yaml.get("services").and_then(|s| s.as_mapping()).into_iter().flatten() {
+    let name = name.as_str().unwrap_or_default();
+
+    for key in ["extends", "network_mode", "networks"] {
+      if service.get(key).is_some() {
+        warnings.push(format!("`{}` of {} is not supported", key, name));
+      }
+    }
+
+    if service.get("ports").and_then(|p| p.as_sequence()).into_iter().flatten().any(|p| p.is_mapping()) {
+      warnings.push(format!("Long syntax of `ports` of {} is published to the host as it is", name));
+    }
+  }
+
+  return warnings;
+}
+
+/// URL of the origin, or the first remote.
+fn repository_of(dir: &Path) -> Option<String> {
+  let remotes = git(dir, &["remote"])?;
+  let remote = remotes.lines().find(|r| *r == "origin").or(remotes.lines().next())?;
+
+  return git(dir, &["remote", "get-url", remote]);
+}
+
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+  let output = process::Command::new("git").arg("-C").arg(dir).args(args).output().ok()?;
+
+  if !output.status.success() {
+    return None;
+  }
+
+  return Some(String::from_utf8_lossy(&output.stdout).trim().to_string()).filter(|s| !s.is_empty());
+}
+
+/// Name of the application from the directory name. It's usable as the label of the domain.
+pub fn name_of(dir: &Path) -> String {
+  let base = dir.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+  let replaced = base.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '-' }).collect::<String>();
+
+  return replaced.split('-').filter(|s| !s.is_empty()).collect::<Vec<&str>>().join("-");
+}
+
+/// Path from the base to the path. Both of them have to be absolute.
+pub fn relative_path(path: &Path, base: &Path) -> PathBuf {
+  let path = path.components().collect::<Vec<Component>>();
+  let base = base.components().collect::<Vec<Component>>();
+  let common = path.iter().zip(base.iter()).take_while(|(a, b)| a == b).count();
+  let mut relative = PathBuf::new();
+
+  for _ in common..base.len() {
+    relative.push("..");
+  }
+
+  path[common..].iter().for_each(|c| relative.push(c));
+
+  if relative.as_os_str().is_empty() {
+    relative.push(".");
+  }
+
+  return relative;
+}
+
+/// Repositories under the directory. The directories in a repository aren't scanned except for the repository of the root.
+pub fn scan(dir: &Path, root: &Path) -> Vec<PathBuf> {
+  if dir.join(".git").exists() && dir != root {
+    return vec![dir.to_path_buf()];
+  }
+
+  let mut entries = match fs::read_dir(dir) {
+    Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_dir()).collect::<Vec<PathBuf>>(),
+    Err(_) => return vec![],
+  };
+
+  entries.sort();
+
+  return entries
+    .into_iter()
+    .filter(|p| p.file_name().is_some_and(|n| !n.to_string_lossy().starts_with('.') && !SKIPPED_DIRS.contains(&n.to_string_lossy().as_ref())))
+    .flat_map(|p| scan(&p, root))
+    .collect();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn unsupported_features_are_warned() {
+    let yaml: Value = serde_yaml::from_str("services:\n  app:\n    extends: base\n    ports:\n      - target: 80\n        published: 8080\n  db:\n    network_mode: host\n").unwrap();
+
+    assert_eq!(
+      warnings_of(&yaml),
+      vec![
+        "`extends` of app is not supported".to_string(),
+        "Long syntax of `ports` of app is published to the host as it is".to_string(),
+        "`network_mode` of db is not supported".to_string(),
+      ]
+    );
+  }
+
+  #[test]
+  fn names_and_paths_are_derived_from_the_directory() {
+    assert_eq!(name_of(Path::new("/src/My_Web.App")), "my-web-app");
+    assert_eq!(relative_path(Path::new("/work/repos/web"), Path::new("/work")), PathBuf::from("repos/web"));
+    assert_eq!(relative_path(Path::new("/src/web"), Path::new("/work/hills")), PathBuf::from("../../src/web"));
+  }
+}
@@ -1,4 +1,5 @@
 use crate::application::Application;
+use crate::config;
 use crate::docker_compose::command;
 use std::path::PathBuf;
 
@@ -17,10 +18,31 @@ pub fn new(app: &Application, files: Vec<Box<PathBuf>>) -> Runner {
 }
 
 impl Runner {
+  pub fn up(&self) {
+    let mut cmd = self.make_command().up();
+
+    if !cmd.status().expect(&format!("Failed to run command : {:?}", cmd)).success() {
+      panic!("Failed to up {}", self.name);
+    }
+  }
+
+  /// Stop and remove the containers. The volumes are removed too if it's specified.
+  pub fn down(&self, volumes: bool) {
+    let mut cmd = self.make_command().down();
+
+    if volumes {
+      cmd.arg("--volumes");
+    }
+
+    if !cmd.status().expect(&format!("Failed to run command : {:?}", cmd)).success() {
+      panic!("Failed to down {}", self.name);
+    }
+  }
+
   pub fn ps(&self) {
     let mut cmd = self.make_command().ps();
 
-    cmd.spawn().expect(&format!("Failed to run command : {:?}", cmd));
+    cmd.status().expect(&format!("Failed to run command : {:?}", cmd));
   }
 
   fn make_command(&self) -> Box<command::Command> {
@@ -30,6 +52,10 @@ impl Runner {
       cmd.add_file(f.clone());
     });
 
+    if let Some(parallel) = config::current().engine_parallel() {
+      cmd.set_parallel(parallel);
+    }
+
     return Box::new(cmd);
   }
 }
@@ -0,0 +1,119 @@
+use crate::config;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Only the bare variables are replaced. The expressions like `{{ app("shared").service("db").fqdn }}` are kept for `[env]`.
+static VARIABLE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}").unwrap());
+
+/// The application TOML in the template directory.
+const APPLICATION_FILE: &str = "application.toml";
+
+/// Files copied into the repository of the application.
+const FILES_DIR: &str = "files";
+
+/// Directories of the templates. The ones under the root take precedence over the ones of the user.
+pub fn dirs() -> Vec<PathBuf> {
+  return vec![config::current().root().join("templates"), config::user_dir().join("templates")];
+}
+
+/// Names of the templates that have the application TOML.
+pub fn names() -> Vec<String> {
+  let mut names = dirs()
+    .iter()
+    .filter_map(|d| fs::read_dir(d).ok())
+    .flatten()
+    .filter_map(|e| e.ok())
+    .filter(|e| e.path().join(APPLICATION_FILE).exists())
+    .map(|e| e.file_name().to_string_lossy().to_string())
+    .collect::<Vec<String>>();
+
+  names.sort();
+  names.dedup();
+
+  return names;
+}
+
+pub fn find(name: &str) -> Result<PathBuf, String> {
+  return dirs().into_iter().map(|d| d.join(name)).find(|d| d.join(APPLICATION_FILE).exists()).ok_or(format!(
+    "Not found the template {} in {}. Available: {}",
+    name,
+    dirs().iter().map(|d| format!("{:?}", d)).collect::<Vec<String>>().join(", "),
+    names().join(", ")
+  ));
+}
+
+/// Replace `{{ <variable> }}` in the template. The unknown variables are errors.
+pub fn render(template: &str, vars: &BTreeMap<String, String>) -> Result<String, String> {
+  let mut rendered = "".to_string();
+  let mut last = 0;
+
+  for caps in VARIABLE.captures_iter(template) {
+    let m = caps.get(0).unwrap();
+
+    rendered.push_str(&template[last..m.start()]);
+    rendered.push_str(vars.get(&caps[1]).ok_or(format!("Unknown variable `{}`", &caps[1]))?);
+    last = m.end();
+  }
+
+  rendered.push_str(&template[last..]);
+
+  return Ok(rendered);
+}
+
+/// Rendered application TOML of the template.
+pub fn application(dir: &Path, vars: &BTreeMap<String, String>) -> Result<String, String> {
+  let f = dir.join(APPLICATION_FILE);
+  let s = fs::read_to_string(&f).map_err(|e| format!("Failed to read {:?} : {}", f, e))?;
+
+  return render(&s, vars).map_err(|e| format!("Failed to render {:?} : {}", f, e));
+}
+
+/// Rendered files of the template by the path relative to the repository.
+pub fn files(dir: &Path, vars: &BTreeMap<String, String>) -> Result<Vec<(PathBuf, String, fs::Permissions)>, String> {
+  let mut files = vec![];
+  let mut dirs = vec![dir.join(FILES_DIR)];
+
+  while let Some(d) = dirs.pop() {
+    let Ok(entries) = fs::read_dir(&d) else {
+      continue;
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+      let path = entry.path();
+
+      if path.is_dir() {
+        dirs.push(path);
+        continue;
+      }
+
+      let s = fs::read_to_string(&path).map_err(|e| format!("Failed to read {:?} : {}", path, e))?;
+      let rendered = render(&s, vars).map_err(|e| format!("Failed to render {:?} : {}", path, e))?;
+      let permissions = entry.metadata().map_err(|e| format!("Failed to read {:?} : {}", path, e))?.permissions();
+
+      files.push((path.strip_prefix(dir.join(FILES_DIR)).unwrap().to_path_buf(), rendered, permissions));
+    }
+  }
+
+  files.sort_by(|a, b| a.0.cmp(&b.0));
+
+  return Ok(files);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn only_the_variables_are_replaced() {
+    let vars = BTreeMap::from([("name".to_string(), "api".to_string())]);
+
+    assert_eq!(
+      render("path = \"repos/{{ name }}\"\nDB = '{{ app(\"shared\").service(\"db\").fqdn }}'\n", &vars),
+      Ok("path = \"repos/api\"\nDB = '{{ app(\"shared\").service(\"db\").fqdn }}'\n".to_string())
+    );
+    assert_eq!(render("{{name}}.{{ ruby }}", &vars), Err("Unknown variable `ruby`".to_string()));
+  }
+}
@@ -1,62 +1,102 @@
-use crate::application::Application;
+use crate::application::dotenv::{self, Layer};
+use crate::application::{self, Application};
 use crate::config;
 use crate::dhcp;
 use crate::dns;
 use crate::docker_compose;
+use path_absolutize::Absolutize;
 use regex::Regex;
+use sha2::{Digest, Sha256};
 use std::collections::BTreeMap;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io;
 use std::io::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-pub struct Synchronizer {
-  app: &Application,
-  original_hash: Option<String>,
+const PLAIN_LAYERS: [Layer; 4] = [Layer::Repository, Layer::Template, Layer::Workspace, Layer::Local];
+
+/// Profile of the skipped services. They aren't started unless the profile is enabled.
+const SKIP_PROFILE: &str = "hills-skip";
+
+pub struct Synchronizer<'a> {
+  app: &'a Application,
+  original_hashes: Option<Vec<String>>,
 }
 
-pub fn new(app: &Application) -> Synchronizer {
-  return Synchronizer { app: app, original_hash: None };
+pub fn new(app: &Application) -> Synchronizer<'_> {
+  return Synchronizer { app: app, original_hashes: None };
 }
 
-impl Synchronizer {
+impl Synchronizer<'_> {
+  /// Whether the copies of the compose files exist and they're generated from the current application TOML and Hills.toml.
   pub fn is_up_to_date(&mut self) -> bool {
-    return self.file_path().exists();
+    let stamp = fs::read_to_string(*self.stamp_file_path()).unwrap_or_default();
+
+    return self.file_paths().iter().all(|f| f.exists()) && stamp.trim() == self.config_hash();
   }
 
   pub fn perform(&mut self) {
-    self.sync_original();
+    for (orig_path, path) in self.original_file_paths().into_iter().zip(self.file_paths()) {
+      self.sync_original(orig_path, path);
+    }
 
     let dns = config::current().dns();
-    let dhcp = self.create_override(&dns);
+    let mut dhcp = self.create_override(&dns);
+
+    self.add_records(&dns, &mut dhcp);
+
+    if self.app.is_shared() {
+      self.check_shared_names(&dns, &dhcp);
+    }
 
     dns.update_config(self.app, dhcp.dns_config());
+    dns.save_leases(self.app, &dhcp);
+    self.create_env_file();
+
+    let stamp = self.stamp_file_path();
+
+    fs::write(*stamp.clone(), format!("{}\n", self.config_hash())).expect(&format!("Failed to write {:?}", stamp));
   }
 
-  pub fn file_path(&mut self) -> Box<PathBuf> {
-    return Box::new(self.app.dist_root().join(format!("{}.yml", self.original_hash())));
+  /// Hash of the configs that the generated files depend on.
+  fn stamp_file_path(&self) -> Box<PathBuf> {
+    return Box::new(self.app.dist_root().join("config.sha256"));
+  }
+
+  fn config_hash(&self) -> String {
+    let mut hasher = Sha256::new();
+
+    for file in [application::file_path(&self.app.name), config::current().path()] {
+      hasher.update(fs::read(*file).unwrap_or_default());
+    }
+
+    return format!("{:x}", hasher.finalize());
+  }
+
+  /// Copies of the original compose files. They're named with the index to keep the order.
+  pub fn file_paths(&mut self) -> Vec<Box<PathBuf>> {
+    return self.original_hashes().iter().enumerate().map(|(i, hash)| Box::new(self.app.dist_root().join(format!("{:02}-{}.yml", i, hash)))).collect();
   }
 
   pub fn override_file_path(&self) -> Box<PathBuf> {
     return Box::new(self.app.dist_root().join("override.yml"));
   }
 
-  fn original_file_path(&self) -> Box<PathBuf> {
-    return Box::new(self.app.root().join("docker-compose.yml"));
+  fn original_file_paths(&self) -> Vec<Box<PathBuf>> {
+    return self.app.compose_file_paths();
   }
 
-  fn sync_original(&mut self) {
-    let orig_path = self.original_file_path();
+  fn sync_original(&self, orig_path: Box<PathBuf>, path: Box<PathBuf>) {
     let read_error_message = format!("Failed to read {:?}", orig_path);
     let orig_file = File::open(*orig_path.clone()).expect(&read_error_message);
     let lines = io::BufReader::new(orig_file).lines();
 
-    let path = self.file_path();
     let write_error_message = format!("Failed to write {:?}", path);
     let file = File::create(*path.clone()).expect(&write_error_message);
     let mut writer = io::BufWriter::new(file);
 
+    let re = Regex::new(r#"^(\s+-\s+['"]?).+:([^-?+])"#).unwrap();
     let mut on_ports = false;
     for line in lines {
       let l = line.expect(&read_error_message);
@@ -73,8 +113,6 @@ impl Synchronizer {
           //   https://docs.docker.com/compose/environment-variables/env-file/
           //
           // TODO: To support longsyntax format
-          let re = Regex::new(r#"^(\s+-\s+['"]?).+:([^-?+])"#).unwrap();
-
           // Removing a part of exposing port to host
           writeln!(writer, "{}", re.replace(&l, "$1$2")).expect(&write_error_message);
         } else {
@@ -98,22 +136,50 @@ impl Synchronizer {
   }
 
   fn create_override(&mut self, dns: &dns::Dns) -> dhcp::Dhcp {
-    let yaml = docker_compose::load(self.file_path());
     let mut dhcp = dns.new_dhcp_for(self.app);
 
-    let orig_services = yaml.services.unwrap_or(BTreeMap::new());
+    // The later file overrides the service in the former one like compose does.
+    let mut orig_services: BTreeMap<String, docker_compose::Service> = BTreeMap::new();
+
+    for file in self.file_paths() {
+      orig_services.extend(docker_compose::load(file).services.unwrap_or_default());
+    }
+
+    let ports = self.app.published_ports();
+    let skipped = self.app.skipped_services();
     // let orig_networks = yaml.networks.unwrap_or(BTreeMap::new());
 
+    let mut env_files: Vec<Box<PathBuf>> = vec![];
+
+    if PLAIN_LAYERS.iter().any(|l| !self.app.raw_env_of(*l).is_empty()) {
+      env_files.push(self.app.env_file_path());
+    }
+
+    if !self.app.raw_env_of(Layer::Secret).is_empty() {
+      env_files.push(self.app.secret_file_path());
+    }
+
+    // It's resolved from the directory of the original compose file if it's relative.
+    let env_file = if env_files.is_empty() {
+      None
+    } else {
+      Some(env_files.iter().map(|f| f.absolutize().unwrap().to_str().unwrap().to_string()).collect())
+    };
+
     let mut services: BTreeMap<String, docker_compose::Service> = BTreeMap::new();
     for (name, service) in orig_services.iter() {
       let orig_name = service.container_name.as_ref().unwrap_or(name);
 
+      let lease = dhcp.assign(orig_name);
+      let resources = self.app.resources_of(orig_name);
+
       let mut nw: BTreeMap<String, docker_compose::Network> = BTreeMap::new();
       nw.insert(
-        dns.name.clone(),
+        dns.name().to_string(),
         docker_compose::Network {
           external: None,
-          ipv4_address: Some(dhcp.assign(orig_name)),
+          ipv4_address: lease.ipv4,
+          ipv6_address: lease.ipv6,
           aliases: None,
         },
       );
@@ -123,6 +189,7 @@ impl Synchronizer {
         docker_compose::Network {
           external: None,
           ipv4_address: None,
+          ipv6_address: None,
           aliases: Some(vec![orig_name.to_string()]),
         },
       );
@@ -131,11 +198,17 @@ impl Synchronizer {
         container_name: Some(format!("{}-{}", self.app.name.clone(), orig_name)),
         build: None,
         volumes: None,
-        ports: None,
+        ports: ports.get(orig_name).cloned(),
+        expose: None,
+        env_file: env_file.clone(),
         networks: Some(docker_compose::ServiceNetworkable::Map(nw)),
-        dns: Some(vec![dns.addr(), dns.root_addr()]),
+        dns: Some(dns.addrs()),
         tty: None,
         stdin_open: None,
+        profiles: if skipped.contains(orig_name) { Some(vec![SKIP_PROFILE.to_string()]) } else { None },
+        cpus: resources.cpus,
+        mem_limit: resources.mem_limit,
+        pids_limit: resources.pids_limit,
       };
 
       services.insert(name.clone(), s);
@@ -144,10 +217,11 @@ impl Synchronizer {
     let mut networks: BTreeMap<String, docker_compose::Network> = BTreeMap::new();
 
     networks.insert(
-      dns.name.clone(),
+      dns.name().to_string(),
       docker_compose::Network {
         external: Some(true),
         ipv4_address: None,
+        ipv6_address: None,
         aliases: None,
       },
     );
@@ -163,25 +237,104 @@ impl Synchronizer {
     return dhcp;
   }
 
-  fn original_hash(&mut self) -> String {
-    if self.original_hash.is_none() {
-      let v = Command::new("shasum")
-        .arg("-a")
-        .arg("256")
-        .arg(self.original_file_path().as_os_str())
-        .output()
-        .expect(&format!("Failed to execute shasum -a 256 {:?}", self.original_file_path().as_os_str()))
-        .stdout
-        .iter()
-        .map(|&x| x as char)
-        .collect::<String>()
-        .split(" ")
-        .collect::<Vec<&str>>()[0]
-        .to_string();
-
-      self.original_hash = Some(v);
-    }
-
-    return self.original_hash.clone().unwrap();
+  /// Merge the layers except for the secrets into the env file.
+  fn create_env_file(&self) {
+    let mut values: BTreeMap<String, String> = BTreeMap::new();
+
+    for layer in PLAIN_LAYERS {
+      values.extend(self.app.env_of(layer));
+    }
+
+    if !values.is_empty() {
+      config::create_file(self.app.env_file_path(), dotenv::render(&values));
+    }
   }
+
+  /// Names of the leases by the service name. The leases are keyed by container_name if it's set.
+  fn lease_names(&self) -> BTreeMap<String, String> {
+    let mut names: BTreeMap<String, String> = BTreeMap::new();
+
+    for file in self.original_file_paths() {
+      for (name, service) in docker_compose::load(file).services.unwrap_or_default() {
+        names.insert(name.clone(), service.container_name.unwrap_or(name));
+      }
+    }
+
+    return names;
+  }
+
+  fn add_records(&self, dns: &dns::Dns, dhcp: &mut dhcp::Dhcp) {
+    let lease_names = self.lease_names();
+
+    for (service, aliases) in self.app.aliases() {
+      let service = lease_names.get(&service).cloned().unwrap_or(service);
+
+      for alias in aliases {
+        if let Err(e) = dhcp.add_alias(&service, &alias) {
+          panic!("Invalid application {} : {}", self.app.name(), e);
+        }
+      }
+    }
+
+    for record in self.app.records() {
+      if let Err(e) = self.app.check_record(&record) {
+        panic!("Invalid application {} : {}", self.app.name(), e);
+      }
+
+      // The address in the shared network has to be in the subnet of the application not to conflict with the others.
+      if let Some(addr) = record.addr().filter(|a| dns.contains(*a) && !dhcp.contains(*a)) {
+        panic!("Invalid application {} : {} of the record {} is out of the subnet of the application", self.app.name(), addr, record.name);
+      }
+
+      if let Err(e) = dhcp.add_record(record) {
+        panic!("Invalid application {} : {}", self.app.name(), e);
+      }
+    }
+  }
+
+  /// The services of the shared applications are published under the same domain of the workspace as the applications.
+  fn check_shared_names(&self, dns: &dns::Dns, dhcp: &dhcp::Dhcp) {
+    let c = config::current();
+    let names = c.application_names().into_iter().chain(c.aliases().into_keys()).collect::<Vec<String>>();
+
+    if let Some(service) = dhcp.leases().keys().find(|s| names.contains(*s)) {
+      panic!("Invalid application {} : {}.{} is the name of the application", self.app.name(), service, dns.domain());
+    }
+
+    for (other, services) in dns.leases() {
+      if other == self.app.name() || !application::load(&other).is_ok_and(|a| a.is_shared()) {
+        continue;
+      }
+
+      if let Some(service) = dhcp.leases().keys().find(|s| services.contains_key(*s)) {
+        panic!("Invalid application {} : {}.{} is already published by {}", self.app.name(), service, dns.domain(), other);
+      }
+    }
+  }
+
+  fn original_hashes(&mut self) -> Vec<String> {
+    if self.original_hashes.is_none() {
+      let hashes = self.original_file_paths().iter().map(|f| hash_of(f)).collect();
+
+      self.original_hashes = Some(hashes);
+    }
+
+    return self.original_hashes.clone().unwrap();
+  }
+}
+
+fn hash_of(file: &Path) -> String {
+  return Command::new("shasum")
+    .arg("-a")
+    .arg("256")
+    .arg(file.as_os_str())
+    .output()
+    .expect(&format!("Failed to execute shasum -a 256 {:?}", file.as_os_str()))
+    .stdout
+    .iter()
+    .map(|&x| x as char)
+    .collect::<String>()
+    .split(" ")
+    .collect::<Vec<&str>>()[0]
+    .to_string();
 }
@@ -0,0 +1,139 @@
+use crate::lima::VmType;
+use crate::vm::{Backend, Capacity, Status};
+use std::process;
+
+pub struct Colima {
+  profile: String,
+  vm_type: VmType,
+  cpus: u32,
+  memory: u32,
+  disk: u32,
+}
+
+pub fn new(vm_type: VmType, cpus: u32, memory: u32, disk: u32) -> Colima {
+  return Colima {
+    profile: "hills".to_string(),
+    vm_type: vm_type,
+    cpus: cpus,
+    memory: memory,
+    disk: disk,
+  };
+}
+
+impl Colima {
+  fn run(&self, cmd: &mut process::Command) -> bool {
+    return cmd.status().expect(&format!("Failed to execute {:?}", cmd)).success();
+  }
+}
+
+/// Status of the profile in the output of `colima list`.
+fn status_of(output: &str, profile: &str) -> Status {
+  let status = output.lines().skip(1).map(|l| l.split_whitespace().collect::<Vec<&str>>()).find(|parts| parts.first() == Some(&profile));
+
+  return match status {
+    Some(parts) => match parts.get(1).map(|s| s.to_lowercase()).as_deref() {
+      Some("running") => Status::Running,
+      Some("stopped") => Status::PowerOff,
+      Some("broken") => Status::Aborted,
+      _ => Status::Unknown,
+    },
+    None => Status::NotCreated,
+  };
+}
+
+impl Backend for Colima {
+  fn name(&self) -> String {
+    return self.profile.clone();
+  }
+
+  fn hostname(&self) -> String {
+    return format!("colima-{}", self.profile);
+  }
+
+  fn requires_guest(&self) -> bool {
+    // Colima exposes the docker socket of the guest to the host.
+    return false;
+  }
+
+  fn render_config(&self) {
+    // Colima is configured by the flags for `colima start`.
+  }
+
+  fn start(&self) {
+    let ok = self.run(
+      process::Command::new("colima")
+        .arg("start")
+        .arg("--profile")
+        .arg(&self.profile)
+        .arg("--vm-type")
+        .arg(self.vm_type.as_str())
+        .arg("--cpu")
+        .arg(self.cpus.to_string())
+        .arg("--memory")
+        .arg(self.memory.to_string())
+        .arg("--disk")
+        .arg(self.disk.to_string()),
+    );
+
+    if !ok {
+      panic!("Failed to start the colima profile {}", self.profile);
+    }
+  }
+
+  fn stop(&self) {
+    if !self.run(process::Command::new("colima").arg("stop").arg("--profile").arg(&self.profile)) {
+      panic!("Failed to stop the colima profile {}", self.profile);
+    }
+  }
+
+  fn delete(&self) {
+    if !self.run(process::Command::new("colima").arg("delete").arg("--force").arg("--profile").arg(&self.profile)) {
+      panic!("Failed to delete the colima profile {}", self.profile);
+    }
+  }
+
+  fn status(&self) -> Status {
+    let output = process::Command::new("colima")
+      .arg("list")
+      .output()
+      .expect("Failed to execute colima list")
+      .stdout
+      .iter()
+      .map(|&x| x as char)
+      .collect::<String>();
+
+    return status_of(&output, &self.profile);
+  }
+
+  fn exec(&self, args: &[String]) -> bool {
+    return self.run(process::Command::new("colima").arg("ssh").arg("--profile").arg(&self.profile).arg("--").args(args));
+  }
+
+  fn ssh(&self) {
+    if !self.run(process::Command::new("colima").arg("ssh").arg("--profile").arg(&self.profile)) {
+      panic!("Failed to ssh to the colima profile {}", self.profile);
+    }
+  }
+
+  /// The memory of colima is in GiB.
+  fn capacity(&self) -> Option<Capacity> {
+    return Some(Capacity {
+      cpus: self.cpus,
+      memory: u64::from(self.memory) * 1024 * 1024 * 1024,
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn transitional_states_are_unknown() {
+    let output = "PROFILE    STATUS      ARCH       CPUS    MEMORY    DISK\nhills      Starting    aarch64    4       8GiB      60GiB\nother      Running     aarch64    2       2GiB      60GiB\n";
+
+    assert_eq!(status_of(output, "hills"), Status::Unknown);
+    assert_eq!(status_of(output, "other"), Status::Running);
+    assert_eq!(status_of(output, "none"), Status::NotCreated);
+  }
+}
@@ -1,147 +1,533 @@
+pub mod editor;
+pub mod layer;
+
+use crate::colima::{self, Colima};
 use crate::dns::{self, Dns};
-use crate::lima::{self, Lima};
+use crate::docker_compose::engine;
+use crate::lima::{self, Lima, VmType};
+use crate::migration;
 use crate::mutagen::{self, Mutagen};
+use crate::resolver::{self, Resolver};
+use crate::vm;
 use garde::Validate;
-use ipnet::Ipv4Net;
-use once_cell::sync::OnceCell;
+use ipnet::{Ipv4Net, Ipv6Net};
+use once_cell::sync::{Lazy, OnceCell};
+use regex::Regex;
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use std::collections::BTreeMap;
+use std::env;
 use std::fs::{self, File};
 use std::io::Write;
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr};
 use std::path::{Path, PathBuf};
+use std::process;
+use toml::Table;
 
 #[derive(Debug)]
 pub struct Config {
   path: Box<PathBuf>,
   values: Values,
+  /// Merged values of all the layers.
+  table: Table,
+  origins: layer::Origins,
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 struct Values {
+  /// Version of the schema.
+  #[garde(range(min = 1, max = VERSION))]
+  #[schemars(range(min = 1, max = "VERSION"))]
+  version: Option<u32>,
   /// Relative path to applications directory from root.
   #[garde(required, length(min = 1))]
+  #[schemars(length(min = 1))]
   app_root: Option<String>,
   #[garde(skip)]
   aliases: Option<BTreeMap<String, String>>,
-  #[garde(required)]
+  #[garde(skip)]
+  vm: Option<VmValues>,
+  #[garde(dive)]
+  engine: Option<EngineValues>,
+  #[garde(required, dive)]
   network: Option<NetworkValues>,
-  #[garde(required)]
+  #[garde(required, dive)]
   lima: Option<LimaValues>,
-  #[garde(required)]
+  #[garde(skip)]
+  colima: Option<ColimaValues>,
+  #[garde(required, dive)]
   mutagen: Option<MutagenValues>,
+  #[garde(skip)]
+  resolver: Option<ResolverValues>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+struct VmValues {
+  /// One of lima, colima or none. Default is lima.
+  #[garde(skip)]
+  backend: Option<vm::Kind>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+struct EngineValues {
+  /// One of docker, docker-compose, podman or nerdctl. Default is docker.
+  #[garde(skip)]
+  kind: Option<engine::Kind>,
+  /// Max number of the parallel operations by compose.
+  #[garde(range(min = 1))]
+  #[schemars(range(min = 1))]
+  parallel: Option<i32>,
 }
 
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 struct NetworkValues {
   #[garde(required, length(min = 1))]
+  #[schemars(length(min = 1))]
   root: Option<String>,
   #[garde(required, length(min = 1))]
+  #[schemars(length(min = 1))]
   name: Option<String>,
   /// Root domain name.
   #[garde(required)]
   domain: Option<String>,
-  /// Root dns server address that's formatted with ipv4.
-  #[garde(required)]
-  dns: Option<Ipv4Addr>,
+  /// Root dns server address that's formatted with ipv4 or ipv6.
   #[garde(required)]
+  dns: Option<IpAddr>,
+  /// Split into /24 for each application. It can be omitted for IPv6-only.
+  #[garde(skip)]
   subnet: Option<Ipv4Net>,
+  /// IPv6 ULA prefix shorter than /64 that's split into /64 for each application. (e.g. "fd00:686c::/48")
+  #[garde(custom(is_ula))]
+  subnet6: Option<Ipv6Net>,
 }
 
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 pub struct LimaValues {
   #[garde(required, length(min = 1))]
+  #[schemars(length(min = 1))]
   root: Option<String>,
-  #[garde(required)]
+  /// One of qemu or vz. Default is qemu.
+  #[garde(skip)]
+  vm_type: Option<VmType>,
+  #[garde(required, range(min = 1))]
+  #[schemars(range(min = 1))]
   cpus: Option<u32>,
-  #[garde(required)]
+  /// Memory size. (e.g. "8GB", "8GiB")
+  #[garde(required, custom(is_size))]
+  #[schemars(regex(path = "SIZE"))]
   memory: Option<String>,
-  #[garde(required)]
+  /// Disk size. (e.g. "30GB", "30GiB")
+  #[garde(required, custom(is_size))]
+  #[schemars(regex(path = "SIZE"))]
   disk: Option<String>,
-  #[garde(required)]
+  #[garde(required, range(min = 1, max = 65535))]
+  #[schemars(range(min = 1, max = 65535))]
   ssh_port: Option<u32>,
+  /// Directories of the host that are mounted in the VM.
+  #[garde(dive)]
+  mounts: Option<Vec<MountValues>>,
+  /// Ports forwarded in addition to the ones in the template.
+  #[garde(dive)]
+  port_forwards: Option<Vec<PortForwardValues>>,
+  /// Environment variables in the VM.
+  #[garde(skip)]
+  env: Option<BTreeMap<String, String>>,
+  #[garde(dive)]
+  images: Option<ImagesValues>,
+  #[garde(dive)]
+  provision: Option<ProvisionValues>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+pub struct ImagesValues {
+  /// One of ubuntu or debian. Default is ubuntu.
+  #[garde(length(min = 1))]
+  #[schemars(length(min = 1))]
+  distro: Option<String>,
+  /// Default is 22.04.
+  #[garde(length(min = 1))]
+  #[schemars(length(min = 1))]
+  version: Option<String>,
+  /// Mirror URLs or local file paths used instead of the default URL.
+  #[garde(dive)]
+  sources: Option<Vec<ImageSourceValues>>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+pub struct ImageSourceValues {
+  /// x86_64 or aarch64. amd64 and arm64 are accepted too.
+  #[garde(required, custom(is_arch))]
+  #[schemars(required, regex(path = "lima::image::ARCH"))]
+  arch: Option<String>,
+  #[garde(required, length(min = 1))]
+  #[schemars(required, length(min = 1))]
+  location: Option<String>,
+  /// Formatted with `sha256:<hex>`.
+  #[garde(custom(is_digest))]
+  #[schemars(regex(path = "DIGEST"))]
+  digest: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+pub struct MountValues {
+  /// Path on the host.
+  #[garde(required, length(min = 1))]
+  #[schemars(required, length(min = 1))]
+  location: Option<String>,
+  /// Path in the VM. Default is the same as `location`.
+  #[garde(skip)]
+  mount_point: Option<String>,
+  #[garde(skip)]
+  writable: Option<bool>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+pub struct PortForwardValues {
+  #[garde(required, range(min = 1, max = 65535))]
+  #[schemars(required, range(min = 1, max = 65535))]
+  guest_port: Option<u32>,
+  #[garde(required, range(min = 1, max = 65535))]
+  #[schemars(required, range(min = 1, max = 65535))]
+  host_port: Option<u32>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+pub struct ProvisionValues {
+  /// Timezone of the VM in the tz database. (e.g. Asia/Tokyo) Default is Etc/UTC.
+  #[garde(custom(is_timezone))]
+  #[schemars(regex(path = "TIMEZONE"))]
+  timezone: Option<String>,
+  /// Packages installed by apt.
+  #[garde(skip)]
+  packages: Option<Vec<String>>,
+  /// Names of the built-in scripts or files to skip. (e.g. "50_apt.sh", "/etc/multipath.conf")
+  #[garde(skip)]
+  disable: Option<Vec<String>>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+pub struct ColimaValues {
+  /// One of qemu or vz. Default is qemu.
+  #[garde(skip)]
+  vm_type: Option<VmType>,
+  #[garde(required, range(min = 1))]
+  #[schemars(range(min = 1))]
+  cpus: Option<u32>,
+  /// Memory size in GiB.
+  #[garde(required, range(min = 1))]
+  #[schemars(range(min = 1))]
+  memory: Option<u32>,
+  /// Disk size in GiB.
+  #[garde(required, range(min = 1))]
+  #[schemars(range(min = 1))]
+  disk: Option<u32>,
 }
 
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct ResolverValues {
+  /// One of systemd-resolved, macos or dnsmasq. Default is detected from the host.
+  kind: Option<resolver::Kind>,
+  /// Address of the DNS server seen from the host. Default is 127.0.0.1.
+  address: Option<Ipv4Addr>,
+  /// Port forwarded to the DNS server. Default is 53.
+  port: Option<u16>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 pub struct MutagenValues {
   #[garde(required, length(min = 1))]
+  #[schemars(length(min = 1))]
   root: Option<String>,
 }
 
+/// Version of the schema of Hills.toml.
+pub const VERSION: u32 = 1;
+
+/// Environment variable of the root directory. It's reserved from the `HILLS_*` values.
+const ROOT_ENV: &str = "HILLS_ROOT";
+
 static INSTANCE: OnceCell<Config> = OnceCell::new();
 
+static DIGEST: Lazy<Regex> = Lazy::new(|| Regex::new(r"^sha256:[0-9a-fA-F]{64}$").unwrap());
+
+static TIMEZONE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z0-9_+-]+(/[A-Za-z0-9_+-]+)*$").unwrap());
+
+static LABEL: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-z0-9]([a-z0-9-]*[a-z0-9])?$").unwrap());
+
+pub static SIZE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)^[0-9]+(\.[0-9]+)?\s*([KMGTP]i?B?|B)?$").unwrap());
+
 pub fn current() -> &'static Config {
   return INSTANCE.get().expect("Config file is not found. Run `hills init` to create it.");
 }
 
-pub fn try_to_setup_from(root: &Path) {
-  let f = file_path(root);
+/// Find the root directory that has Hills.toml. The context is used as it is if it's given, otherwise `HILLS_ROOT` or the current directory and its parents.
+pub fn find_root(context: Option<&Path>) -> Option<PathBuf> {
+  let start = match (context, env::var_os(ROOT_ENV)) {
+    (Some(dir), _) => return file_path(dir).exists().then(|| dir.to_path_buf()),
+    (None, Some(dir)) if !dir.is_empty() => return file_path(Path::new(&dir)).exists().then(|| PathBuf::from(dir)),
+    _ => env::current_dir().ok()?,
+  };
 
-  if !f.exists() {
-    return;
+  return start.ancestors().find(|dir| file_path(dir).exists()).map(|dir| dir.to_path_buf());
+}
+
+pub fn try_to_setup_from(context: Option<&Path>) {
+  if let Some(root) = find_root(context) {
+    INSTANCE.set(load_from(&root)).unwrap();
   }
+}
+
+/// JSON Schema of Hills.toml with the doc comments and the constraints.
+pub fn schema() -> RootSchema {
+  let mut schema = schema_for!(Values);
 
-  INSTANCE.set(load_from(root)).unwrap();
+  schema.schema.metadata().title = Some("Hills.toml".to_string());
+
+  return schema;
 }
 
 pub fn create(root: &Path) {
-  let v = Values {
+  create_file(file_path(root), toml::to_string(&defaults()).unwrap());
+}
+
+/// Directory of the config for the user. (e.g. ~/.config/hills)
+pub fn user_dir() -> Box<PathBuf> {
+  let dir = match env::var_os("XDG_CONFIG_HOME") {
+    Some(v) if !v.is_empty() => PathBuf::from(v),
+    _ => dirs::home_dir().expect("Not found the home directory").join(".config"),
+  };
+
+  return Box::new(dir.join("hills"));
+}
+
+/// Config file of the user layer.
+pub fn user_file_path() -> Box<PathBuf> {
+  return Box::new(user_dir().join("config.toml"));
+}
+
+fn defaults() -> Values {
+  return Values {
+    version: Some(VERSION),
     app_root: Some("applications".to_string()),
     aliases: None,
+    vm: Some(VmValues { backend: Some(vm::Kind::Lima) }),
+    engine: Some(EngineValues {
+      kind: Some(engine::Kind::Docker),
+      parallel: None,
+    }),
     network: Some(NetworkValues {
       root: Some("dns".to_string()),
       name: Some("hills".to_string()),
       domain: Some("local".to_string()),
       subnet: Some("172.31.0.0/16".parse().unwrap()),
-      dns: Some(Ipv4Addr::new(8, 8, 8, 8)),
+      dns: Some(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8))),
+      subnet6: None,
     }),
     lima: Some(LimaValues {
       root: Some("lima".to_string()),
+      vm_type: Some(VmType::Qemu),
       cpus: Some(2),
       memory: Some("8GB".to_string()),
       disk: Some("30GB".to_string()),
       ssh_port: Some(2222),
+      mounts: None,
+      port_forwards: None,
+      env: None,
+      images: None,
+      provision: Some(ProvisionValues {
+        timezone: Some("Etc/UTC".to_string()),
+        packages: Some(default_packages()),
+        disable: None,
+      }),
     }),
+    colima: None,
     mutagen: Some(MutagenValues { root: Some("mutagen".to_string()) }),
+    resolver: None,
   };
+}
+
+/// Check the name that's used as the label of the domain.
+pub fn is_label(name: &str) -> Result<(), String> {
+  if !LABEL.is_match(name) {
+    return Err(format!("`{}` has to be lowercase letters, digits and hyphens", name));
+  }
 
-  create_file(file_path(root), toml::to_string(&v).unwrap());
+  return Ok(());
 }
 
-pub fn set_alias(original: &Option<String>, alias: &Option<String>) {
-  let root = current().root();
-  let mut c = load_from(root.as_ref());
+/// Add the alias of the application. It can't be the name of the other application or the alias of it.
+pub fn add_alias(name: &str, alias: &str) {
+  let c = current();
+  let original = c.resolve(name);
 
-  match original {
-    Some(orig) => {
-      if let Some(v) = alias {
-        if c.values.aliases.is_none() {
-          c.values.aliases = Some(BTreeMap::new());
-        }
+  is_label(alias).unwrap_or_else(|e| panic!("{}", e));
 
-        c.values.aliases.as_mut().unwrap().insert(v.to_string(), orig.to_string());
-      } else if let Some(aliases) = c.values.aliases.as_mut() {
-        let mut filtered: BTreeMap<String, String> = BTreeMap::new();
+  if c.application_names().iter().any(|n| n == alias) {
+    panic!("{} is the name of the application", alias);
+  }
 
-        for (k, v) in aliases.iter() {
-          if v.eq(orig) {
-            continue;
-          }
+  match c.aliases().get(alias) {
+    Some(o) if o.eq(&original) => {
+      println!("{} is already an alias of {}", alias, original);
+      return;
+    }
+    Some(o) => panic!("{} is already an alias of {}", alias, o),
+    None => {}
+  }
 
-          filtered.insert(k.to_string(), v.to_string());
-        }
+  let mut e = editor::open(file_path(c.root().as_ref()));
 
-        c.values.aliases = Some(filtered);
-      }
+  e.set(&format!("aliases.{}", alias), toml_edit::Value::from(original.as_str())).unwrap_or_else(|err| panic!("{}", err));
+  save(e);
+}
+
+pub fn remove_alias(alias: &str) {
+  let c = current();
+  let mut e = editor::open(file_path(c.root().as_ref()));
+
+  if !c.aliases().contains_key(alias) {
+    panic!("Not found alias {}", alias);
+  }
+
+  if !e.unset(&format!("aliases.{}", alias)).unwrap_or_else(|err| panic!("{}", err)) {
+    panic!("{} isn't in {:?}. Remove it from the layer where it comes from", alias, e.path());
+  }
+
+  save(e);
+}
+
+/// Remove the aliases of the application from Hills.toml.
+pub fn remove_aliases_of(name: &str) {
+  let c = current();
+  let mut e = editor::open(file_path(c.root().as_ref()));
+  let aliases = c.aliases_of(name);
+
+  if aliases.is_empty() {
+    return;
+  }
+
+  // The table is removed if no alias is left not to leave the empty `[aliases]`.
+  if aliases.len() == c.aliases().len() {
+    e.unset("aliases").unwrap_or_else(|err| panic!("{}", err));
+  } else {
+    for alias in aliases {
+      e.unset(&format!("aliases.{}", alias)).unwrap_or_else(|err| panic!("{}", err));
     }
-    None => c.values.aliases = None,
   }
 
-  create_file(file_path(root.as_ref()), toml::to_string(&c.values).unwrap());
+  save(e);
+}
+
+/// Point the aliases of the application in Hills.toml to the new name.
+pub fn rename_aliases(from: &str, to: &str) {
+  let c = current();
+  let mut e = editor::open(file_path(c.root().as_ref()));
+  let aliases = c.aliases_of(from);
+
+  if aliases.is_empty() {
+    return;
+  }
+
+  for alias in aliases {
+    e.set(&format!("aliases.{}", alias), toml_edit::Value::from(to)).unwrap_or_else(|err| panic!("{}", err));
+  }
+
+  save(e);
+}
+
+/// Value of the dotted key in the merged config.
+pub fn get(key: &str) -> Option<&'static toml::Value> {
+  let mut value = current().table.get(key.split('.').next().unwrap())?;
+
+  for k in key.split('.').skip(1) {
+    value = value.get(k)?;
+  }
+
+  return Some(value);
+}
+
+/// Set the value of the dotted key in Hills.toml. It's checked with the rules of the config before saving.
+pub fn set(key: &str, value: &str) {
+  let root = current().root();
+  let mut e = editor::open(file_path(root.as_ref()));
+
+  e.set(key, editor::parse_value(value)).unwrap_or_else(|err| panic!("{}", err));
+
+  let updated = try_load_from(root.as_ref(), Some(&e.content())).unwrap_or_else(|err| panic!("{}", err));
+
+  if !updated.is_known(key) {
+    panic!("Unknown key {}", key);
+  }
+
+  save(e);
+  updated.warn_if_overridden(key);
+}
+
+/// Remove the dotted key from Hills.toml.
+pub fn unset(key: &str) {
+  let root = current().root();
+  let mut e = editor::open(file_path(root.as_ref()));
+
+  if !e.unset(key).unwrap_or_else(|err| panic!("{}", err)) {
+    panic!("Not found {} in {:?}", key, e.path());
+  }
+
+  let updated = try_load_from(root.as_ref(), Some(&e.content())).unwrap_or_else(|err| panic!("{}", err));
+
+  save(e);
+  updated.warn_if_overridden(key);
+}
+
+/// Edit Hills.toml with the editor. The draft is saved into Hills.toml only if it's valid.
+pub fn edit() {
+  let root = current().root();
+  let f = file_path(root.as_ref());
+  let draft = current().dist_root().join("Hills.toml");
+  let editor = env::var("VISUAL").or(env::var("EDITOR")).unwrap_or("vi".to_string());
+
+  fs::copy(f.as_ref(), &draft).expect(&format!("Failed to copy {:?}", f));
+
+  let status = process::Command::new("sh")
+    .arg("-c")
+    .arg(format!("{} \"$1\"", editor))
+    .arg("sh")
+    .arg(&draft)
+    .status()
+    .expect(&format!("Failed to run {}", editor));
+
+  if !status.success() {
+    panic!("Failed to edit {:?} : The draft is kept in {:?}", f, draft);
+  }
+
+  let s = fs::read_to_string(&draft).expect(&format!("Failed to read {:?}", draft));
+
+  if let Err(err) = try_load_from(root.as_ref(), Some(&s)) {
+    panic!("{} : The draft is kept in {:?}", err, draft);
+  }
+
+  if s != fs::read_to_string(f.as_ref()).expect(&format!("Failed to read {:?}", f)) {
+    create_file(f, s);
+  }
+
+  fs::remove_file(&draft).expect(&format!("Failed to remove {:?}", draft));
+}
+
+fn save(e: editor::Editor) {
+  create_file(e.path(), e.content());
 }
 
 pub fn create_file(f: Box<PathBuf>, s: String) {
@@ -152,23 +538,170 @@ pub fn create_file(f: Box<PathBuf>, s: String) {
   println!("Saved {:?}", f);
 }
 
+pub fn is_size(value: &Option<String>, _: &()) -> garde::Result {
+  match value {
+    Some(v) if !SIZE.is_match(v) => Err(garde::Error::new(format!("`{}` is not a size like 8GB or 8GiB", v))),
+    _ => Ok(()),
+  }
+}
+
+/// Bytes of the size. The units are binary for both of "8GB" and "8GiB" like lima and compose.
+pub fn bytes_of(size: &str) -> Option<u64> {
+  if !SIZE.is_match(size) {
+    return None;
+  }
+
+  let s = size.trim().to_uppercase();
+  let digits = s.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(s.len());
+  let value: f64 = s[..digits].parse().ok()?;
+  let exponent = match s[digits..].trim().chars().next() {
+    Some('K') => 1,
+    Some('M') => 2,
+    Some('G') => 3,
+    Some('T') => 4,
+    Some('P') => 5,
+    _ => 0,
+  };
+
+  return Some((value * 1024f64.powi(exponent)) as u64);
+}
+
+fn is_digest(value: &Option<String>, _: &()) -> garde::Result {
+  match value {
+    Some(v) if !DIGEST.is_match(v) => Err(garde::Error::new(format!("`{}` is not formatted with sha256:<hex>", v))),
+    _ => Ok(()),
+  }
+}
+
+fn is_arch(value: &Option<String>, _: &()) -> garde::Result {
+  match value.as_deref().map(lima::image::normalize_arch) {
+    Some(Err(e)) => Err(garde::Error::new(e)),
+    _ => Ok(()),
+  }
+}
+
+fn is_timezone(value: &Option<String>, _: &()) -> garde::Result {
+  match value {
+    Some(v) if !TIMEZONE.is_match(v) => Err(garde::Error::new(format!("`{}` is not a name of the tz database", v))),
+    _ => Ok(()),
+  }
+}
+
+fn is_ula(value: &Option<Ipv6Net>, _: &()) -> garde::Result {
+  match value {
+    Some(v) if (v.addr().segments()[0] & 0xfe00) != 0xfc00 => Err(garde::Error::new(format!("`{}` is not a ULA prefix in fc00::/7", v))),
+    // The first /64 is reserved, so /64 leaves no subnet for the applications.
+    Some(v) if v.prefix_len() >= 64 => Err(garde::Error::new(format!("`{}` has to be shorter than /64 to split into /64 for the applications", v))),
+    _ => Ok(()),
+  }
+}
+
+fn default_packages() -> Vec<String> {
+  return ["build-essential", "uidmap", "dbus-user-session", "net-tools"].iter().map(|s| s.to_string()).collect();
+}
+
 fn file_path(root: &Path) -> Box<PathBuf> {
   return Box::new(root.join("Hills.toml"));
 }
 
+/// Layers of the config files in the order of the precedence. They're ignored if they don't exist.
+fn layer_files(root: &Path) -> Vec<(layer::Origin, Box<PathBuf>)> {
+  return vec![
+    (layer::Origin::Workspace, file_path(root)),
+    (layer::Origin::Local, Box::new(root.join("Hills.local.toml"))),
+    (layer::Origin::User, user_file_path()),
+  ];
+}
+
 fn load_from(root: &Path) -> Config {
+  return try_load_from(root, None).unwrap_or_else(|e| panic!("{}", e));
+}
+
+/// Load the config from the layers. The content of Hills.toml is replaced with the given one to check it before saving.
+pub fn try_load_from(root: &Path, workspace: Option<&str>) -> Result<Config, String> {
   let f = file_path(root);
-  let s = fs::read_to_string(*f.clone()).expect(&format!("Failed to read {:?}", f));
-  let values: Values = toml::from_str(&s).expect(&format!("Failed to load config from {:?}", f));
+  let mut table = Table::new();
+  let mut origins = layer::Origins::new();
+  let mut defaults = Table::try_from(defaults()).unwrap();
+
+  // The subnet isn't defaulted so that the network can be IPv6-only.
+  if let Some(network) = defaults.get_mut("network").and_then(|n| n.as_table_mut()) {
+    network.remove("subnet");
+  }
+
+  layer::merge(&mut table, defaults, layer::Origin::Default, &mut origins, "");
+
+  for (origin, file) in layer_files(root) {
+    let s = match (origin, workspace) {
+      (layer::Origin::Workspace, Some(s)) => s.to_string(),
+      _ if !file.exists() => continue,
+      _ => fs::read_to_string(file.as_ref()).map_err(|e| format!("Failed to read {:?} : {}", file, e))?,
+    };
+    // The old Hills.toml is migrated in memory until it's rewritten by `hills migrate`.
+    let s = match origin {
+      layer::Origin::Workspace => migration::migrate_str(migration::Schema::Config, &s, VERSION).map_err(|e| format!("Failed to load config from {:?} : {}", file, e))?,
+      _ => s,
+    };
+    let t: Table = toml::from_str(&s).map_err(|e| format!("Failed to load config from {:?} : {}", file, e))?;
+
+    layer::merge(&mut table, t, origin, &mut origins, "");
+  }
+
+  layer::merge(&mut table, layer::from_env(env::vars(), &[ROOT_ENV]), layer::Origin::Env, &mut origins, "");
+
+  let values: Values = table.clone().try_into().map_err(|e| format!("Failed to load config from {:?} : {}", f, e))?;
 
   if let Err(e) = values.validate(&()) {
-    panic!("Invalid config {:?} : {e}", f);
+    return Err(format!("Invalid config {:?} : {}", f, e.to_string().trim_end()));
   }
 
-  return Config { path: f, values: values };
+  return Ok(Config {
+    path: f,
+    values: values,
+    table: table,
+    origins: origins,
+  });
 }
 
 impl Config {
+  /// Whether the dotted key is a field of the config. The unknown keys are dropped by the deserialization.
+  fn is_known(&self, key: &str) -> bool {
+    let mut value = toml::Value::try_from(&self.values).unwrap();
+
+    for k in key.split('.') {
+      value = match value.get(k) {
+        Some(v) => v.clone(),
+        None => return false,
+      };
+    }
+
+    return true;
+  }
+
+  fn warn_if_overridden(&self, key: &str) {
+    if let Some(origin) = self.origins.get(key).filter(|o| **o > layer::Origin::Workspace) {
+      eprintln!("{} is overridden by {}", key, origin);
+    }
+  }
+
+  /// Effective values by the dotted key with the layer where they come from.
+  pub fn entries(&self) -> Vec<(String, toml::Value, layer::Origin)> {
+    return self
+      .table
+      .iter()
+      .flat_map(|(k, v)| layer::leaves(v, k))
+      .map(|(k, v)| {
+        let origin = self.origins.get(&k).copied().unwrap_or(layer::Origin::Default);
+
+        (k, v, origin)
+      })
+      .collect();
+  }
+
+  pub fn path(&self) -> Box<PathBuf> {
+    return self.path.clone();
+  }
+
   pub fn root(&self) -> Box<PathBuf> {
     return Box::new(self.path.parent().unwrap().to_path_buf());
   }
@@ -184,29 +717,106 @@ impl Config {
   }
 
   pub fn domain(&self) -> String {
-    return self.values.network.unwrap().domain.unwrap().trim().to_string();
+    return self.values.network.as_ref().unwrap().domain.as_ref().unwrap().trim().to_string();
   }
 
   pub fn app_root(&self) -> Box<PathBuf> {
-    return Box::new(self.path.parent().unwrap().join(&self.values.app_root.unwrap()));
+    return Box::new(self.path.parent().unwrap().join(self.values.app_root.as_ref().unwrap()));
   }
 
   pub fn dns(&self) -> Dns {
-    let c = self.values.network.unwrap();
+    let c = self.values.network.as_ref().unwrap();
+
+    if c.subnet.is_none() && c.subnet6.is_none() {
+      panic!("Invalid config {:?} : network.subnet or network.subnet6 is required", self.path);
+    }
 
-    return dns::new(c.root.unwrap().to_string(), c.name.unwrap().to_string(), c.domain.unwrap().to_string(), c.subnet.unwrap().clone(), c.dns.unwrap().clone());
+    return dns::new(c.root.clone().unwrap(), c.name.clone().unwrap(), c.domain.clone().unwrap(), c.subnet, c.subnet6, c.dns.unwrap());
   }
 
   pub fn lima(&self) -> Lima {
-    let c = self.values.lima.unwrap();
+    let c = self.values.lima.as_ref().unwrap();
+    let p = c.provision.as_ref();
+    let provision = lima::provision::Settings {
+      timezone: p.and_then(|v| v.timezone.clone()).unwrap_or("Etc/UTC".to_string()),
+      packages: p.and_then(|v| v.packages.clone()).unwrap_or(default_packages()),
+      engine: self.engine_kind(),
+      disable: p.and_then(|v| v.disable.clone()).unwrap_or_default(),
+    };
+
+    let mut lima = lima::new(
+      c.root.clone().unwrap(),
+      c.vm_type.unwrap_or(VmType::Qemu),
+      c.cpus.unwrap(),
+      c.memory.clone().unwrap(),
+      c.disk.clone().unwrap(),
+      c.ssh_port.unwrap(),
+      provision,
+    );
+
+    c.mounts.iter().flatten().for_each(|m| {
+      lima.add_mount(m.location.clone().unwrap(), m.mount_point.clone(), m.writable.unwrap_or(false));
+    });
+    c.port_forwards.iter().flatten().for_each(|p| {
+      lima.add_port_forward(p.guest_port.unwrap(), p.host_port.unwrap());
+    });
+    c.env.iter().flatten().for_each(|(k, v)| {
+      lima.set_env(k.clone(), v.clone());
+    });
+
+    if let Some(i) = c.images.as_ref() {
+      let sources = i
+        .sources
+        .iter()
+        .flatten()
+        .map(|s| lima::image::Source {
+          arch: s.arch.clone().unwrap(),
+          location: s.location.clone().unwrap(),
+          digest: s.digest.clone(),
+        })
+        .collect();
+
+      lima.set_images(lima::image::new(i.distro.clone().unwrap_or("ubuntu".to_string()), i.version.clone().unwrap_or("22.04".to_string()), sources));
+    }
+
+    return lima;
+  }
+
+  pub fn vm_kind(&self) -> vm::Kind {
+    return self.values.vm.as_ref().and_then(|v| v.backend).unwrap_or(vm::Kind::Lima);
+  }
 
-    return lima::new(c.root.unwrap(), c.cpus.unwrap(), c.memory.unwrap(), c.disk.unwrap(), c.ssh_port.unwrap());
+  pub fn engine_kind(&self) -> engine::Kind {
+    return self.values.engine.as_ref().and_then(|v| v.kind).unwrap_or(engine::Kind::Docker);
+  }
+
+  pub fn engine_parallel(&self) -> Option<i32> {
+    return self.values.engine.as_ref().and_then(|v| v.parallel);
+  }
+
+  pub fn colima(&self) -> Colima {
+    let c = self.values.colima.as_ref().expect("Not found [colima] in the config. It's required for the colima backend.");
+
+    if let Err(e) = c.validate(&()) {
+      panic!("Invalid config [colima] : {e}");
+    }
+
+    return colima::new(c.vm_type.unwrap_or(VmType::Qemu), c.cpus.unwrap(), c.memory.unwrap(), c.disk.unwrap());
+  }
+
+  pub fn resolver(&self, kind: Option<resolver::Kind>) -> Resolver {
+    let c = self.values.resolver.as_ref();
+    let kind = kind.or(c.and_then(|v| v.kind)).unwrap_or_else(resolver::detect);
+    let address = c.and_then(|v| v.address).unwrap_or(Ipv4Addr::LOCALHOST);
+    let port = c.and_then(|v| v.port).unwrap_or(53);
+
+    return resolver::new(kind, self.domain(), address, port);
   }
 
   pub fn mutagen(&self) -> Mutagen {
-    let c = self.values.mutagen.unwrap();
+    let c = self.values.mutagen.as_ref().unwrap();
 
-    return mutagen::new(c.root.unwrap());
+    return mutagen::new(c.root.clone().unwrap());
   }
 
   pub fn application_names(&self) -> Vec<String> {
@@ -233,6 +843,11 @@ impl Config {
   }
 
   pub fn resolve(&self, name: &str) -> String {
+    return self.try_resolve(name).unwrap_or_else(|| panic!("Not found application : {}", name));
+  }
+
+  /// Name of the application for the name or the alias if it exists.
+  pub fn try_resolve(&self, name: &str) -> Option<String> {
     let resolved = match &self.values.aliases {
       Some(aliases) => match aliases.get(name) {
         Some(v) => v.clone(),
@@ -242,24 +857,126 @@ impl Config {
     };
 
     if !self.application_names().contains(&resolved) {
-      panic!("Not found application : {}", name);
+      return None;
     }
 
-    return resolved;
+    return Some(resolved);
   }
 
-  pub fn get_alias(&self, original: &str) -> Option<String> {
-    match &self.values.aliases {
-      Some(aliases) => {
-        for (k, v) in aliases.iter() {
-          if v.eq(original) {
-            return Some(k.to_string());
-          }
-        }
+  /// Application names by the alias.
+  pub fn aliases(&self) -> BTreeMap<String, String> {
+    return self.values.aliases.clone().unwrap_or_default();
+  }
 
-        None
-      }
-      None => None,
-    }
+  /// Aliases of the application in the order of the name.
+  pub fn aliases_of(&self, original: &str) -> Vec<String> {
+    return self.aliases().into_iter().filter(|(_, v)| v.eq(original)).map(|(k, _)| k).collect();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::application;
+  use serde::de::DeserializeOwned;
+
+  /// Samples that garde and the schema judge differently. The values that can't be deserialized are invalid for garde.
+  fn drifts_of<T: DeserializeOwned + Validate<Context = ()>>(schema: RootSchema, samples: Vec<String>) -> Vec<String> {
+    let schema = jsonschema::JSONSchema::compile(&serde_json::to_value(schema).unwrap()).unwrap();
+
+    return samples
+      .into_iter()
+      .filter_map(|sample| {
+        let by_garde = toml::from_str::<T>(&sample).is_ok_and(|v| v.validate(&()).is_ok());
+        let by_schema = schema.is_valid(&serde_json::to_value(toml::from_str::<Table>(&sample).unwrap()).unwrap());
+
+        (by_garde != by_schema).then(|| format!("{:?} : garde says {} but the schema says {}", sample, by_garde, by_schema))
+      })
+      .collect();
+  }
+
+  /// Hills.toml of the defaults with the value. The file is complete since the layers are merged before garde.
+  fn config_with(value: &str) -> String {
+    let mut table = Table::try_from(defaults()).unwrap();
+
+    layer::merge(&mut table, toml::from_str(value).unwrap(), layer::Origin::Workspace, &mut layer::Origins::new(), "");
+
+    return toml::to_string(&table).unwrap();
+  }
+
+  #[test]
+  fn ula_prefix_is_split_into_the_subnets() {
+    let ula = |s: &str| is_ula(&Some(s.parse().unwrap()), &());
+
+    assert!(ula("fd00:686c::/48").is_ok());
+    assert!(ula("fd00:686c::/63").is_ok());
+    assert!(ula("fd00:686c::/64").is_err());
+    assert!(ula("2001:db8::/48").is_err());
+  }
+
+  #[test]
+  fn schema_follows_the_validation() {
+    // The custom validators that the schema can't express (e.g. is_ula) and the skipped tables aren't sampled.
+    let config = [
+      "",
+      "version = 2",
+      "version = 0",
+      "app_root = \"\"",
+      "[engine]\nparallel = 0",
+      "[network]\nroot = \"\"",
+      "[network]\nname = \"\"",
+      "[lima]\ncpus = 0",
+      "[lima]\nmemory = \"8 gigabytes\"",
+      "[lima]\ndisk = \"30GiB\"",
+      "[lima]\nssh_port = 65536",
+      "[[lima.mounts]]\nwritable = true",
+      "[[lima.mounts]]\nlocation = \"\"",
+      "[[lima.mounts]]\nlocation = \"~/src\"",
+      "[[lima.port_forwards]]\nguest_port = 0\nhost_port = 8080",
+      "[[lima.port_forwards]]\nguest_port = 80",
+      "[lima.images]\ndistro = \"\"",
+      "[lima.images]\nversion = \"\"",
+      "[[lima.images.sources]]\narch = \"arm64\"\nlocation = \"/srv/ubuntu.img\"\ndigest = \"sha256:0000000000000000000000000000000000000000000000000000000000000000\"",
+      "[[lima.images.sources]]\narch = \"riscv64\"\nlocation = \"/srv/ubuntu.img\"",
+      "[[lima.images.sources]]\narch = \"arm64\"",
+      "[[lima.images.sources]]\narch = \"arm64\"\nlocation = \"/srv/ubuntu.img\"\ndigest = \"md5:0\"",
+      "[lima.provision]\ntimezone = \"Asia/Tokyo\"",
+      "[lima.provision]\ntimezone = \"Asia Tokyo\"",
+      "[mutagen]\nroot = \"\"",
+    ];
+    let application = [
+      "",
+      "[compose]\nfiles = []",
+      "[compose]\nfiles = [\"\"]",
+      "[compose]\nfiles = [\"compose.yaml\"]",
+      "[hooks]\npre_up = [\"\"]",
+      "[hooks]\npost_update = [\"./bin/setup\"]",
+      "[sync]\nmode = \"two-way-resolved\"",
+      "[sync]\nmode = \"both-ways\"",
+      "[resources]\ncpus = 0",
+      "[resources]\ncpus = 0.5\nmem_limit = \"512m\"\npids_limit = 100",
+      "[resources]\nmem_limit = \"half\"",
+      "[resources]\npids_limit = 0",
+      "[resources.services.worker]\ncpus = 0",
+      "[[dns.records]]\nname = \"api\"\ntype = \"A\"\nvalue = \"192.168.5.2\"",
+      "[[dns.records]]\nname = \"my api\"\ntype = \"A\"\nvalue = \"192.168.5.2\"",
+      "[[dns.records]]\nname = \"\"\ntype = \"A\"\nvalue = \"192.168.5.2\"",
+      "[[dns.records]]\nname = \"api\"\ntype = \"A\"\nvalue = \"\"",
+      "[[dns.records]]\ntype = \"A\"\nvalue = \"192.168.5.2\"",
+    ];
+    let versions = ["version = 2\npath = \"repos/web\"\n", "version = 0\npath = \"repos/web\"\n", "version = 1\n", "version = 1\npath = \"\"\n"];
+
+    assert_eq!(drifts_of::<Values>(schema(), config.iter().map(|v| config_with(v)).collect()), Vec::<String>::new());
+    assert_eq!(
+      drifts_of::<application::Values>(
+        application::schema(),
+        application
+          .iter()
+          .map(|v| format!("version = {}\npath = \"repos/web\"\n{}", application::VERSION, v))
+          .chain(versions.iter().map(|v| v.to_string()))
+          .collect()
+      ),
+      Vec::<String>::new()
+    );
   }
 }
@@ -0,0 +1,115 @@
+use std::fs;
+use std::path::PathBuf;
+use toml_edit::{Document, Item, Table, TableLike, Value};
+
+/// TOML file edited with the comments and the order of the keys preserved.
+pub struct Editor {
+  path: Box<PathBuf>,
+  doc: Document,
+}
+
+/// Editor of the file. It's empty if the file doesn't exist.
+pub fn open(path: Box<PathBuf>) -> Editor {
+  let doc = match path.exists() {
+    true => fs::read_to_string(path.as_ref())
+      .expect(&format!("Failed to read {:?}", path))
+      .parse::<Document>()
+      .expect(&format!("Failed to parse {:?}", path)),
+    false => Document::new(),
+  };
+
+  return Editor { path: path, doc: doc };
+}
+
+/// Value parsed as TOML or used as a string. (e.g. `4`, `"8GB"`, `["a", "b"]` or `8GB`)
+pub fn parse_value(s: &str) -> Value {
+  return format!("v = {}", s).parse::<Document>().ok().and_then(|doc| doc.get("v").and_then(|v| v.as_value()).cloned()).unwrap_or_else(|| Value::from(s));
+}
+
+fn split(key: &str) -> Result<Vec<&str>, String> {
+  let keys = key.split('.').collect::<Vec<&str>>();
+
+  if keys.iter().any(|k| k.is_empty()) {
+    return Err(format!("`{}` is not a dotted key like lima.cpus", key));
+  }
+
+  return Ok(keys);
+}
+
+impl Editor {
+  pub fn path(&self) -> Box<PathBuf> {
+    return self.path.clone();
+  }
+
+  /// Set the value. The missing tables are created.
+  pub fn set(&mut self, key: &str, value: Value) -> Result<(), String> {
+    let keys = split(key)?;
+    let mut t: &mut dyn TableLike = self.doc.as_table_mut();
+
+    for k in keys[..keys.len() - 1].iter() {
+      let mut table = Table::new();
+
+      table.set_implicit(true);
+      t = t.entry(k).or_insert(Item::Table(table)).as_table_like_mut().ok_or(format!("`{}` in {} is not a table", k, key))?;
+    }
+
+    let last = keys[keys.len() - 1];
+
+    match t.get_mut(last) {
+      Some(item) if item.is_value() => {
+        // Keep the comments around the value.
+        let decor = item.as_value().unwrap().decor().clone();
+        let mut value = value;
+
+        *value.decor_mut() = decor;
+        *item = Item::Value(value);
+      }
+      _ => {
+        t.insert(last, Item::Value(value));
+      }
+    }
+
+    return Ok(());
+  }
+
+  /// Remove the value. It returns false if the key isn't in the file.
+  pub fn unset(&mut self, key: &str) -> Result<bool, String> {
+    let keys = split(key)?;
+    let mut t: &mut dyn TableLike = self.doc.as_table_mut();
+
+    for k in keys[..keys.len() - 1].iter() {
+      t = match t.get_mut(k).and_then(|i| i.as_table_like_mut()) {
+        Some(t) => t,
+        None => return Ok(false),
+      };
+    }
+
+    return Ok(t.remove(keys[keys.len() - 1]).is_some());
+  }
+
+  pub fn content(&self) -> String {
+    return self.doc.to_string();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn comments_and_order_are_preserved() {
+    let mut editor = Editor {
+      path: Box::new(PathBuf::from("Hills.toml")),
+      doc: "# Workspace\napp_root = \"applications\"\n\n[lima]\ncpus = 2 # cores\nmemory = \"8GB\"\n".parse().unwrap(),
+    };
+
+    editor.set("lima.cpus", parse_value("4")).unwrap();
+    editor.set("lima.disk", parse_value("30GB")).unwrap();
+    editor.set("engine.parallel", parse_value("2")).unwrap();
+
+    assert_eq!(editor.unset("lima.memory"), Ok(true));
+    assert_eq!(editor.unset("colima.cpus"), Ok(false));
+    assert!(editor.set("app_root.x", parse_value("1")).is_err());
+    assert_eq!(editor.content(), "# Workspace\napp_root = \"applications\"\n\n[lima]\ncpus = 4 # cores\ndisk = \"30GB\"\n\n[engine]\nparallel = 2\n");
+  }
+}
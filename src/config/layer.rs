@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use toml::{Table, Value};
+
+/// Prefix of the environment variables. The keys are separated by `__`. (e.g. `HILLS_LIMA__CPUS=4` for `lima.cpus`)
+const ENV_PREFIX: &str = "HILLS_";
+
+/// Where the value comes from in the order of the precedence from low to high.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Origin {
+  Default,
+  /// Hills.toml
+  Workspace,
+  /// Hills.local.toml that isn't tracked.
+  Local,
+  /// config.toml under the config directory of the user. (e.g. ~/.config/hills/config.toml)
+  User,
+  Env,
+}
+
+impl fmt::Display for Origin {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let s = match self {
+      Origin::Default => "default".to_string(),
+      Origin::Workspace => "Hills.toml".to_string(),
+      Origin::Local => "Hills.local.toml".to_string(),
+      // XDG_CONFIG_HOME is respected.
+      Origin::User => super::user_file_path().to_string_lossy().to_string(),
+      Origin::Env => "env".to_string(),
+    };
+
+    return write!(f, "{}", s);
+  }
+}
+
+/// Origins by the dotted key of the leaf value.
+pub type Origins = BTreeMap<String, Origin>;
+
+/// Merge the table deeply into the base. The arrays are replaced, not merged.
+pub fn merge(base: &mut Table, overlay: Table, origin: Origin, origins: &mut Origins, prefix: &str) {
+  for (key, value) in overlay {
+    let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+
+    match (base.get_mut(&key), value) {
+      (Some(Value::Table(b)), Value::Table(o)) => merge(b, o, origin, origins, &path),
+      (_, value) => {
+        origins.retain(|k, _| !k.starts_with(&format!("{}.", path)));
+
+        for (leaf, _) in leaves(&value, &path) {
+          origins.insert(leaf, origin);
+        }
+
+        base.insert(key, value);
+      }
+    }
+  }
+}
+
+/// Leaf values with the dotted keys.
+pub fn leaves(value: &Value, path: &str) -> Vec<(String, Value)> {
+  return match value {
+    Value::Table(t) => t.iter().flat_map(|(k, v)| leaves(v, &format!("{}.{}", path, k))).collect(),
+    _ => vec![(path.to_string(), value.clone())],
+  };
+}
+
+/// Table from the `HILLS_*` environment variables. The value is parsed as TOML or used as a string.
+pub fn from_env(vars: impl Iterator<Item = (String, String)>, reserved: &[&str]) -> Table {
+  let mut table = Table::new();
+
+  for (name, value) in vars {
+    if reserved.contains(&name.as_str()) {
+      continue;
+    }
+
+    let keys = match name.strip_prefix(ENV_PREFIX) {
+      Some(k) if !k.is_empty() => k.to_lowercase().split("__").map(|s| s.to_string()).collect::<Vec<String>>(),
+      _ => continue,
+    };
+    let parsed = toml::from_str::<Table>(&format!("v = {}", value)).ok().and_then(|mut t| t.remove("v")).unwrap_or(Value::String(value));
+
+    let mut t = &mut table;
+
+    for key in keys[..keys.len() - 1].iter() {
+      t = t.entry(key.clone()).or_insert(Value::Table(Table::new())).as_table_mut().expect(&format!("Conflicted environment variable {}", name));
+    }
+
+    t.insert(keys[keys.len() - 1].clone(), parsed);
+  }
+
+  return table;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn later_layers_override_the_leaves() {
+    let mut base: Table = toml::from_str("[lima]\ncpus = 2\nmemory = \"8GB\"\n").unwrap();
+    let mut origins: Origins = BTreeMap::from([("lima.cpus".to_string(), Origin::Default), ("lima.memory".to_string(), Origin::Default)]);
+    let env = from_env(
+      vec![
+        ("HILLS_LIMA__CPUS".to_string(), "4".to_string()),
+        ("HILLS_LIMA__VM_TYPE".to_string(), "vz".to_string()),
+        ("HILLS_ROOT".to_string(), "/tmp".to_string()),
+      ]
+      .into_iter(),
+      &["HILLS_ROOT"],
+    );
+
+    merge(&mut base, env, Origin::Env, &mut origins, "");
+
+    assert_eq!(base["lima"]["cpus"].as_integer(), Some(4));
+    assert_eq!(base["lima"]["vm_type"].as_str(), Some("vz"));
+    assert_eq!(base["lima"]["memory"].as_str(), Some("8GB"));
+    assert!(base.get("root").is_none());
+    assert_eq!(origins["lima.cpus"], Origin::Env);
+    assert_eq!(origins["lima.memory"], Origin::Default);
+  }
+}
@@ -1,39 +1,192 @@
-use ipnet::{IpAdd, Ipv4Net};
+use crate::dns::record::Record;
+use ipnet::{IpAdd, Ipv4Net, Ipv6Net};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
 use std::collections::BTreeMap;
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Addresses assigned to the service. Either of them is omitted if the subnet isn't configured.
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Lease {
+  pub ipv4: Option<Ipv4Addr>,
+  pub ipv6: Option<Ipv6Addr>,
+}
+
+impl Lease {
+  pub fn addrs(&self) -> Vec<IpAddr> {
+    return self.ipv4.map(IpAddr::V4).into_iter().chain(self.ipv6.map(IpAddr::V6)).collect();
+  }
+}
 
 pub struct Dhcp {
   domain: String,
-  subnet: Ipv4Net,
-  services: BTreeMap<String, Ipv4Addr>,
+  /// Domains of the aliases of the application. The services are published under them too.
+  other_domains: Vec<String>,
+  subnet: Option<Ipv4Net>,
+  subnet6: Option<Ipv6Net>,
+  services: BTreeMap<String, Lease>,
+  aliases: BTreeMap<String, String>,
+  records: Vec<Record>,
 }
 
-pub fn new(domain: String, subnet: Ipv4Net) -> Dhcp {
+pub fn new(domain: String, subnet: Option<Ipv4Net>, subnet6: Option<Ipv6Net>) -> Dhcp {
   return Dhcp {
     domain: domain,
+    other_domains: vec![],
     subnet: subnet,
+    subnet6: subnet6,
     services: BTreeMap::new(),
+    aliases: BTreeMap::new(),
+    records: vec![],
   };
 }
 
 impl Dhcp {
-  pub fn assign(&mut self, service: &str) -> Ipv4Addr {
+  pub fn assign(&mut self, service: &str) -> Lease {
     let index = self.services.keys().count() + 1;
-    let addr = self.subnet.addr().saturating_add(index as u32);
+    let lease = Lease {
+      ipv4: self.subnet.map(|s| s.addr().saturating_add(index as u32)),
+      ipv6: self.subnet6.map(|s| s.addr().saturating_add(index as u128)),
+    };
+
+    self.services.insert(service.to_string(), lease);
+
+    return lease;
+  }
+
+  /// Assigned addresses by the service name.
+  pub fn leases(&self) -> &BTreeMap<String, Lease> {
+    return &self.services;
+  }
+
+  /// Whether the address is in the subnet of the application.
+  pub fn contains(&self, addr: IpAddr) -> bool {
+    return match addr {
+      IpAddr::V4(v) => self.subnet.is_some_and(|s| s.contains(&v)),
+      IpAddr::V6(v) => self.subnet6.is_some_and(|s| s.contains(&v)),
+    };
+  }
+
+  pub fn add_domain(&mut self, domain: String) {
+    if domain != self.domain && !self.other_domains.contains(&domain) {
+      self.other_domains.push(domain);
+    }
+  }
 
-    self.services.insert(service.to_string(), addr.clone());
+  /// Resolve the alias to the addresses of the service.
+  pub fn add_alias(&mut self, service: &str, alias: &str) -> Result<(), String> {
+    if !self.services.contains_key(service) {
+      return Err(format!("Not found the service {} for the alias {}", service, alias));
+    }
 
-    return addr;
+    if self.services.contains_key(alias) || self.aliases.contains_key(alias) {
+      return Err(format!("The alias {} is already used", alias));
+    }
+
+    self.aliases.insert(alias.to_string(), service.to_string());
+
+    return Ok(());
+  }
+
+  pub fn add_record(&mut self, record: Record) -> Result<(), String> {
+    record.check()?;
+
+    if let Some(addr) = record.addr() {
+      if self.services.values().any(|l| l.addrs().contains(&addr)) {
+        return Err(format!("{} of the record {} is already assigned to the service", addr, record.name));
+      }
+    }
+
+    self.records.push(record);
+
+    return Ok(());
   }
 
   pub fn dns_config(&self) -> String {
-    let local_data_part = self
-      .services
-      .keys()
-      .map(|s| format!("  local-data: \"{}.{}.local. A {}\"\n", s, self.domain, self.services.get(s).unwrap()))
-      .collect::<Vec<String>>()
-      .join("");
+    let mut local_data_part = "".to_string();
+    for domain in [&self.domain].into_iter().chain(self.other_domains.iter()) {
+      let names = self.services.keys().map(|s| (s, s)).chain(self.aliases.iter());
+
+      for (name, s) in names {
+        let lease = self.services.get(s).unwrap();
+
+        if let Some(addr) = lease.ipv4 {
+          local_data_part.push_str(&format!("  local-data: \"{}.{}. A {}\"\n", name, domain, addr));
+        }
+
+        if let Some(addr) = lease.ipv6 {
+          local_data_part.push_str(&format!("  local-data: \"{}.{}. AAAA {}\"\n", name, domain, addr));
+        }
+      }
+    }
+
+    for record in self.records.iter() {
+      local_data_part.push_str(&record.local_data(&self.domain));
+    }
 
     return format!("server:\n{}", local_data_part);
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::dns::record;
+
+  #[test]
+  fn addresses_are_assigned_from_each_subnet() {
+    let mut dhcp = new("web.local".to_string(), Some("172.31.1.0/24".parse().unwrap()), Some("fd00:686c:0:1::/64".parse().unwrap()));
+    let lease = dhcp.assign("app");
+
+    assert_eq!(lease.ipv4, Some(Ipv4Addr::new(172, 31, 1, 1)));
+    assert_eq!(lease.ipv6, Some("fd00:686c:0:1::1".parse().unwrap()));
+    assert_eq!(dhcp.dns_config(), "server:\n  local-data: \"app.web.local. A 172.31.1.1\"\n  local-data: \"app.web.local. AAAA fd00:686c:0:1::1\"\n");
+  }
+
+  #[test]
+  fn ipv6_only_has_no_a_records() {
+    let mut dhcp = new("web.local".to_string(), None, Some("fd00:686c:0:1::/64".parse().unwrap()));
+
+    dhcp.assign("app");
+    dhcp.assign("db");
+
+    assert_eq!(dhcp.leases()["db"].addrs(), vec!["fd00:686c:0:1::2".parse::<IpAddr>().unwrap()]);
+    assert!(!dhcp.dns_config().contains(" A "));
+  }
+
+  #[test]
+  fn aliases_and_records_are_added() {
+    let mut dhcp = new("web.local".to_string(), Some("172.31.1.0/24".parse().unwrap()), None);
+
+    dhcp.assign("app");
+
+    assert!(dhcp.add_alias("app", "api").is_ok());
+    assert!(dhcp.add_alias("db", "postgres").is_err());
+    assert!(dhcp.add_alias("app", "api").is_err());
+
+    let record = |value: &str| Record {
+      name: "host".to_string(),
+      kind: record::Kind::A,
+      value: value.to_string(),
+    };
+
+    assert!(dhcp.add_record(record("172.31.1.1")).is_err());
+    assert!(dhcp.add_record(record("192.168.5.2")).is_ok());
+    assert_eq!(
+      dhcp.dns_config(),
+      "server:\n  local-data: \"app.web.local. A 172.31.1.1\"\n  local-data: \"api.web.local. A 172.31.1.1\"\n  local-data: \"host.web.local. A 192.168.5.2\"\n"
+    );
+  }
+
+  #[test]
+  fn services_are_published_under_all_the_domains() {
+    let mut dhcp = new("web.local".to_string(), Some("172.31.1.0/24".parse().unwrap()), None);
+
+    dhcp.assign("app");
+    dhcp.add_domain("www.local".to_string());
+    dhcp.add_domain("web.local".to_string());
+
+    assert_eq!(dhcp.dns_config(), "server:\n  local-data: \"app.web.local. A 172.31.1.1\"\n  local-data: \"app.www.local. A 172.31.1.1\"\n");
+  }
+}
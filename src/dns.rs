@@ -1,49 +1,101 @@
+pub mod record;
+
 use crate::application::Application;
 use crate::config;
 use crate::dhcp;
-use crate::docker_compose;
-use ipnet::{IpAdd, Ipv4Net};
+use crate::docker_compose::{self, command, engine};
+use ipnet::{IpAdd, Ipv4Net, Ipv6Net};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::collections::BTreeMap;
 use std::fs;
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::path::PathBuf;
 
 pub struct Dns {
   path: String,
   name: String,
   domain: String,
-  subnet: Ipv4Net,
-  root: Ipv4Addr,
+  subnet: Option<Ipv4Net>,
+  /// ULA prefix that's split into /64 for each application.
+  subnet6: Option<Ipv6Net>,
+  root: IpAddr,
 }
 
-pub fn new(path: String, name: String, domain: String, subnet: Ipv4Net, root: Ipv4Addr) -> Dns {
+pub fn new(path: String, name: String, domain: String, subnet: Option<Ipv4Net>, subnet6: Option<Ipv6Net>, root: IpAddr) -> Dns {
   return Dns {
     path: path,
     name: name,
     domain: domain,
     subnet: subnet,
+    subnet6: subnet6,
     root: root,
   };
 }
 
 impl Dns {
+  pub fn name(&self) -> &str {
+    return self.name.as_str();
+  }
+
   pub fn domain(&self) -> &str {
     return self.domain.as_str();
   }
 
-  pub fn addr(&self) -> Ipv4Addr {
-    return self.subnet.addr().saturating_add(2);
+  pub fn addr(&self) -> Option<Ipv4Addr> {
+    return self.subnet.map(|s| s.addr().saturating_add(2));
+  }
+
+  pub fn addr6(&self) -> Option<Ipv6Addr> {
+    return self.subnet6.map(|s| s.addr().saturating_add(2));
   }
 
-  pub fn root_addr(&self) -> Ipv4Addr {
-    return self.root.clone();
+  pub fn root_addr(&self) -> IpAddr {
+    return self.root;
+  }
+
+  /// Whether the address is in the shared network.
+  pub fn contains(&self, addr: IpAddr) -> bool {
+    return match addr {
+      IpAddr::V4(v) => self.subnet.is_some_and(|s| s.contains(&v)),
+      IpAddr::V6(v) => self.subnet6.is_some_and(|s| s.contains(&v)),
+    };
+  }
+
+  /// Addresses of the DNS servers for the containers in the order of the lookup.
+  pub fn addrs(&self) -> Vec<IpAddr> {
+    let mut list: Vec<IpAddr> = vec![];
+
+    list.extend(self.addr().map(IpAddr::V4));
+    list.extend(self.addr6().map(IpAddr::V6));
+    list.push(self.root);
+
+    return list;
   }
 
   pub fn new_dhcp_for(&self, app: &Application) -> dhcp::Dhcp {
-    return dhcp::new(app.domain(), self.find_or_create_subnet_for(app));
+    let subnet = self.subnet.map(|s| self.find_or_create_subnet_for(app, "subnets.toml", s.subnets(24).unwrap()));
+    let subnet6 = self.subnet6.map(|s| self.find_or_create_subnet_for(app, "subnets6.toml", s.subnets(64).unwrap()));
+
+    let domains = app.domains();
+    let mut dhcp = dhcp::new(domains[0].clone(), subnet, subnet6);
+
+    domains.into_iter().skip(1).for_each(|d| dhcp.add_domain(d));
+
+    return dhcp;
   }
 
   pub fn setup(&self) {
+    let engine = engine::current();
+
+    if !engine.network_exists(&self.name) {
+      let mut cmd = engine.network_create(&self.name, self.subnet.as_ref(), self.subnet6.as_ref());
+
+      if !cmd.status().expect(&format!("Failed to execute {:?}", cmd)).success() {
+        panic!("Failed to create the network {}", self.name);
+      }
+    }
+
     if !self.docker_compose_path().exists() {
       self.create_docker_compose();
     }
@@ -53,12 +105,78 @@ impl Dns {
     }
   }
 
+  /// Restart the DNS server to load the updated config.
+  pub fn reload(&self) {
+    let mut cmd = self.command().restart();
+
+    if !cmd.status().expect(&format!("Failed to execute {:?}", cmd)).success() {
+      panic!("Failed to reload the DNS server");
+    }
+  }
+
   pub fn update_config(&self, app: &Application, value: String) {
     let file = self.dist_root().join(format!("{}.conf", app.name()));
 
     config::create_file(Box::new(file), value);
   }
 
+  pub fn save_leases(&self, app: &Application, dhcp: &dhcp::Dhcp) {
+    let mut leases = self.leases();
+
+    leases.insert(app.name().to_string(), dhcp.leases().clone());
+    config::create_file(self.leases_path(), toml::to_string(&leases).unwrap());
+  }
+
+  /// Move the subnets and the leases to the new name of the application. The config is removed to be regenerated.
+  pub fn rename(&self, from: &str, to: &str) {
+    self.move_entries(from, Some(to));
+  }
+
+  /// Release the subnets and the leases of the application, and remove the config.
+  pub fn release(&self, app: &str) {
+    self.move_entries(app, None);
+  }
+
+  fn move_entries(&self, from: &str, to: Option<&str>) {
+    for name in ["subnets.toml", "subnets6.toml", "leases.toml"] {
+      let file = Box::new(self.root().join(name));
+
+      if !file.exists() {
+        continue;
+      }
+
+      let s = fs::read_to_string(file.as_ref()).expect(&format!("Failed to read {:?}", file));
+      let mut table: toml::Table = toml::from_str(&s).expect(&format!("Failed to load {:?}", file));
+
+      if let Some(v) = table.remove(from) {
+        if let Some(to) = to {
+          table.insert(to.to_string(), v);
+        }
+
+        config::create_file(file, toml::to_string(&table).unwrap());
+      }
+    }
+
+    let conf = self.dist_root().join(format!("{}.conf", from));
+
+    if conf.exists() {
+      fs::remove_file(&conf).expect(&format!("Failed to remove {:?}", conf));
+    }
+  }
+
+  /// Assigned addresses by the application name and the service name.
+  pub fn leases(&self) -> BTreeMap<String, BTreeMap<String, dhcp::Lease>> {
+    let file = self.leases_path();
+
+    if !file.exists() {
+      return BTreeMap::new();
+    }
+
+    let s = fs::read_to_string(file.as_ref()).expect(&format!("Failed to read {:?}", file));
+
+    return toml::from_str(&s).expect(&format!("Failed to load leases from {:?}", file));
+  }
+
   pub fn clear(&self) {
     let dir = self.root();
 
@@ -76,7 +194,8 @@ impl Dns {
       self.name.clone(),
       docker_compose::Network {
         external: None,
-        ipv4_address: Some(self.addr().clone()),
+        ipv4_address: self.addr(),
+        ipv6_address: self.addr6(),
         aliases: None,
       },
     );
@@ -92,10 +211,16 @@ impl Dns {
         // TODO: Making the dist path be programmatic
         volumes: Some(vec!["../.dist/unbound.conf.d:/etc/unbound/unbound.conf.d".to_string()]),
         ports: Some(vec!["53:53".to_string(), "53:53/udp".to_string()]),
+        expose: None,
+        env_file: None,
         networks: Some(docker_compose::ServiceNetworkable::Map(service_networks)),
         dns: None,
         tty: None,
         stdin_open: None,
+        profiles: None,
+        cpus: None,
+        mem_limit: None,
+        pids_limit: None,
       },
     );
 
@@ -104,6 +229,7 @@ impl Dns {
       docker_compose::Network {
         external: Some(true),
         ipv4_address: None,
+        ipv6_address: None,
         aliases: None,
       },
     );
@@ -126,6 +252,7 @@ impl Dns {
   interface: 0.0.0.0
   interface: ::0
   access-control: 0.0.0.0/0 allow
+  access-control: ::0/0 allow
 
   local-zone: "local." transparent
 "#
@@ -134,27 +261,21 @@ impl Dns {
     config::create_file(self.base_config_path(), s);
   }
 
-  fn find_or_create_subnet_for(&self, app: &Application) -> Ipv4Net {
-    let file = self.root().join("subnets.toml");
+  /// Find the subnet of the application in the file or allocate the free one. The first one is reserved for the DNS server.
+  fn find_or_create_subnet_for<N>(&self, app: &Application, name: &str, candidates: impl Iterator<Item = N>) -> N
+  where
+    N: Serialize + DeserializeOwned + Copy + PartialEq,
+  {
+    let file = self.root().join(name);
     let key = app.name();
-    let mut subnets: BTreeMap<String, Ipv4Net> = BTreeMap::new();
-
-    if file.exists() {
-      let s = fs::read_to_string(file.clone()).expect(&format!("Failed to read {:?}", file));
-      subnets = toml::from_str(&s).expect(&format!("Failed to load config from {:?}", file));
-    }
+    let mut subnets: BTreeMap<String, N> = self.read_subnets(name);
 
-    return match subnets.get(&key) {
-      Some(v) => v.clone(),
+    return match subnets.get(key) {
+      Some(v) => *v,
       None => {
-        let subnet = self
-          .subnet
-          .subnets(24)
-          .unwrap()
-          .find(|s| !self.subnet.addr().eq(&s.addr()) && !subnets.values().any(|v| v.eq(s)))
-          .expect("Not found avaiable subnet! Please run stop --all"); // TODO
-
-        subnets.insert(key, subnet.clone());
+        let subnet = candidates.skip(1).find(|s| !subnets.values().any(|v| v.eq(s))).expect("Not found avaiable subnet! Please run stop --all"); // TODO
+
+        subnets.insert(key.to_string(), subnet);
         config::create_file(Box::new(file), toml::to_string(&subnets).unwrap());
 
         subnet
@@ -162,6 +283,40 @@ impl Dns {
     };
   }
 
+  /// IPv4 subnets by the application name.
+  pub fn subnets(&self) -> BTreeMap<String, Ipv4Net> {
+    return self.read_subnets("subnets.toml");
+  }
+
+  /// IPv6 subnets by the application name.
+  pub fn subnets6(&self) -> BTreeMap<String, Ipv6Net> {
+    return self.read_subnets("subnets6.toml");
+  }
+
+  fn read_subnets<N: DeserializeOwned>(&self, name: &str) -> BTreeMap<String, N> {
+    let file = self.root().join(name);
+
+    if !file.exists() {
+      return BTreeMap::new();
+    }
+
+    let s = fs::read_to_string(file.clone()).expect(&format!("Failed to read {:?}", file));
+
+    return toml::from_str(&s).expect(&format!("Failed to load config from {:?}", file));
+  }
+
+  fn command(&self) -> command::Command {
+    let mut cmd = command::new(format!("{}-dns", self.name), self.root());
+
+    cmd.add_file(self.docker_compose_path());
+
+    return cmd;
+  }
+
+  fn leases_path(&self) -> Box<PathBuf> {
+    return Box::new(self.root().join("leases.toml"));
+  }
+
   fn docker_compose_path(&self) -> Box<PathBuf> {
     return Box::new(self.root().join("docker-compose.yml"));
   }
@@ -171,7 +326,7 @@ impl Dns {
   }
 
   fn root(&self) -> Box<PathBuf> {
-    let dir = config::current().root().join(self.path);
+    let dir = config::current().root().join(&self.path);
 
     if !dir.exists() {
       fs::create_dir_all(dir.clone()).expect(&format!("Failed to create {:?}", dir));
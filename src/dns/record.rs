@@ -0,0 +1,161 @@
+use garde::Validate;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Names without spaces and quotes. They're written in the quoted config of unbound.
+static NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^[^\s"']*$"#).unwrap());
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Kind {
+  A,
+  Aaaa,
+  Cname,
+  Txt,
+  /// The value is formatted with `<priority> <weight> <port> <target>`.
+  Srv,
+}
+
+/// Extra record of the application. The name is relative to the domain of the application unless it ends with `.`.
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug, Clone)]
+pub struct Record {
+  #[garde(length(min = 1), custom(is_name))]
+  #[schemars(length(min = 1), regex(path = "NAME"))]
+  pub name: String,
+  #[serde(rename = "type")]
+  #[garde(skip)]
+  pub kind: Kind,
+  #[garde(length(min = 1))]
+  #[schemars(length(min = 1))]
+  pub value: String,
+}
+
+fn is_name(value: &str, _: &()) -> garde::Result {
+  if !NAME.is_match(value) {
+    return Err(garde::Error::new(format!("`{}` is not a domain name", value)));
+  }
+
+  return Ok(());
+}
+
+/// Qualify the name with the domain. `@` is the domain itself.
+pub fn qualify(name: &str, domain: &str) -> String {
+  if name == "@" {
+    return format!("{}.", domain);
+  }
+
+  if name.ends_with('.') {
+    return name.to_string();
+  }
+
+  return format!("{}.{}.", name, domain);
+}
+
+impl Record {
+  /// Whether the name is in one of the domains. The relative names are in the domain that they're qualified with.
+  pub fn is_in(&self, domains: &[String]) -> bool {
+    if self.name == "@" || !self.name.ends_with('.') {
+      return true;
+    }
+
+    let name = self.name.trim_end_matches('.');
+
+    return domains.iter().any(|d| name == d || name.ends_with(&format!(".{}", d)));
+  }
+
+  /// Check the value for the type.
+  pub fn check(&self) -> Result<(), String> {
+    let ok = match self.kind {
+      Kind::A => self.value.parse::<Ipv4Addr>().is_ok(),
+      Kind::Aaaa => self.value.parse::<Ipv6Addr>().is_ok(),
+      Kind::Cname => is_name(&self.value, &()).is_ok(),
+      // The TXT record is quoted with `'` in the config of unbound.
+      Kind::Txt => !self.value.contains('\''),
+      Kind::Srv => {
+        let parts = self.value.split_whitespace().collect::<Vec<&str>>();
+
+        parts.len() == 4 && parts[..3].iter().all(|p| p.parse::<u16>().is_ok())
+      }
+    };
+
+    if !ok {
+      return Err(format!("`{}` is not a value of {:?} record {}", self.value, self.kind, self.name));
+    }
+
+    return Ok(());
+  }
+
+  /// Address of A or AAAA record.
+  pub fn addr(&self) -> Option<IpAddr> {
+    return match self.kind {
+      Kind::A | Kind::Aaaa => self.value.parse().ok(),
+      _ => None,
+    };
+  }
+
+  pub fn local_data(&self, domain: &str) -> String {
+    let name = qualify(&self.name, domain);
+
+    return match self.kind {
+      Kind::A => format!("  local-data: \"{} A {}\"\n", name, self.value),
+      Kind::Aaaa => format!("  local-data: \"{} AAAA {}\"\n", name, self.value),
+      Kind::Cname => format!("  local-data: \"{} CNAME {}\"\n", name, qualify(&self.value, domain)),
+      Kind::Txt => format!("  local-data: '{} TXT \"{}\"'\n", name, self.value.replace('"', "\\\"")),
+      Kind::Srv => {
+        let parts = self.value.split_whitespace().collect::<Vec<&str>>();
+
+        format!("  local-data: \"{} SRV {} {} {} {}\"\n", name, parts[0], parts[1], parts[2], qualify(parts[3], domain))
+      }
+    };
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn record(name: &str, kind: Kind, value: &str) -> Record {
+    return Record {
+      name: name.to_string(),
+      kind: kind,
+      value: value.to_string(),
+    };
+  }
+
+  #[test]
+  fn names_are_qualified_with_the_domain() {
+    assert_eq!(record("api", Kind::A, "192.168.5.2").local_data("checkout.local"), "  local-data: \"api.checkout.local. A 192.168.5.2\"\n");
+    assert_eq!(
+      record("db", Kind::Cname, "postgres.shared.local.").local_data("checkout.local"),
+      "  local-data: \"db.checkout.local. CNAME postgres.shared.local.\"\n"
+    );
+    assert_eq!(record("@", Kind::Txt, "v=1").local_data("checkout.local"), "  local-data: 'checkout.local. TXT \"v=1\"'\n");
+    assert_eq!(
+      record("_http._tcp", Kind::Srv, "0 5 8080 app").local_data("checkout.local"),
+      "  local-data: \"_http._tcp.checkout.local. SRV 0 5 8080 app.checkout.local.\"\n"
+    );
+  }
+
+  #[test]
+  fn values_are_checked_for_the_type() {
+    assert!(record("api", Kind::A, "fd00::1").check().is_err());
+    assert!(record("api", Kind::Aaaa, "fd00::1").check().is_ok());
+    assert!(record("_http._tcp", Kind::Srv, "0 5 app").check().is_err());
+    assert!(record("@", Kind::Txt, "it's").check().is_err());
+  }
+
+  #[test]
+  fn absolute_names_are_in_the_domains() {
+    let domains = vec!["checkout.local".to_string()];
+
+    assert!(record("api", Kind::A, "192.168.5.2").is_in(&domains));
+    assert!(record("api.checkout.local.", Kind::A, "192.168.5.2").is_in(&domains));
+    assert!(record("checkout.local.", Kind::A, "192.168.5.2").is_in(&domains));
+    assert!(!record("api.other.local.", Kind::A, "192.168.5.2").is_in(&domains));
+    assert!(!record("postgres.local.", Kind::A, "192.168.5.2").is_in(&domains));
+    assert!(!record("xcheckout.local.", Kind::A, "192.168.5.2").is_in(&domains));
+  }
+}
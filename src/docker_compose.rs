@@ -1,11 +1,13 @@
 pub mod command;
+pub mod engine;
 
 use serde::{Deserialize, Serialize};
-use serde_with::skip_serializing_none;
+use serde_with::formats::PreferMany;
+use serde_with::{serde_as, skip_serializing_none, DisplayFromStr, OneOrMany, PickFirst};
 use std::collections::BTreeMap;
 use std::fs::{self, File};
 use std::io::prelude::*;
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::path::PathBuf;
 
 #[skip_serializing_none]
@@ -16,6 +18,7 @@ pub struct Yaml {
   pub networks: Option<BTreeMap<String, Network>>,
 }
 
+#[serde_as]
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Service {
@@ -23,10 +26,22 @@ pub struct Service {
   pub build: Option<ServiceBuild>,
   pub volumes: Option<Vec<String>>,
   pub ports: Option<Vec<String>>,
+  pub expose: Option<Vec<String>>,
+  /// A file or the list of the files. It's written as the list.
+  #[serde_as(as = "Option<OneOrMany<_, PreferMany>>")]
+  #[serde(default)]
+  pub env_file: Option<Vec<String>>,
   pub networks: Option<ServiceNetworkable>,
-  pub dns: Option<Vec<Ipv4Addr>>,
+  pub dns: Option<Vec<IpAddr>>,
   pub tty: Option<String>,
   pub stdin_open: Option<String>,
+  pub profiles: Option<Vec<String>>,
+  /// A number or the string of the number. (e.g. 0.5, "0.5")
+  #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+  #[serde(default)]
+  pub cpus: Option<f64>,
+  pub mem_limit: Option<String>,
+  pub pids_limit: Option<i64>,
 }
 
 #[skip_serializing_none]
@@ -37,6 +52,7 @@ pub struct ServiceBuild {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
 pub enum ServiceNetworkable {
   List(Vec<String>),
   Map(BTreeMap<String, Network>),
@@ -47,6 +63,7 @@ pub enum ServiceNetworkable {
 pub struct Network {
   pub external: Option<bool>,
   pub ipv4_address: Option<Ipv4Addr>,
+  pub ipv6_address: Option<Ipv6Addr>,
   pub aliases: Option<Vec<String>>,
 }
 
@@ -64,3 +81,27 @@ impl Yaml {
     f.flush().expect(&error_message);
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn env_file_is_a_file_or_the_list() {
+    let yaml: Yaml = serde_yaml::from_str("services:\n  web:\n    env_file: .env\n  worker:\n    env_file:\n      - .env\n      - worker.env\n").unwrap();
+    let services = yaml.services.unwrap();
+
+    assert_eq!(services["web"].env_file, Some(vec![".env".to_string()]));
+    assert_eq!(services["worker"].env_file, Some(vec![".env".to_string(), "worker.env".to_string()]));
+  }
+
+  #[test]
+  fn cpus_is_a_number_or_the_string() {
+    let yaml: Yaml = serde_yaml::from_str("services:\n  web:\n    cpus: 1.5\n  worker:\n    cpus: \"0.5\"\n").unwrap();
+    let services = yaml.services.unwrap();
+
+    assert_eq!(services["web"].cpus, Some(1.5));
+    assert_eq!(services["worker"].cpus, Some(0.5));
+    assert!(serde_yaml::from_str::<Yaml>("services:\n  web:\n    cpus: half\n").is_err());
+  }
+}
@@ -1,8 +1,9 @@
+use super::engine::{self, Engine};
 use std::path::PathBuf;
 use std::process;
-// use tap::prelude::*;
 
 pub struct Command {
+  engine: Box<dyn Engine>,
   project_name: String,
   project_directory: Box<PathBuf>,
   files: Vec<Box<PathBuf>>,
@@ -10,7 +11,12 @@ pub struct Command {
 }
 
 pub fn new(name: String, dir: Box<PathBuf>) -> Command {
+  return with(engine::current(), name, dir);
+}
+
+pub fn with(engine: Box<dyn Engine>, name: String, dir: Box<PathBuf>) -> Command {
   return Command {
+    engine: engine,
     project_name: name,
     project_directory: dir,
     files: vec![],
@@ -29,25 +35,61 @@ impl Command {
     return self;
   }
 
+  pub fn up(&self) -> Box<process::Command> {
+    let mut cmd = self.make("up");
+
+    cmd.arg("-d");
+
+    return cmd;
+  }
+
+  pub fn down(&self) -> Box<process::Command> {
+    return self.make("down");
+  }
+
+  pub fn restart(&self) -> Box<process::Command> {
+    return self.make("restart");
+  }
+
   pub fn ps(&self) -> Box<process::Command> {
     return self.make("ps");
   }
 
+  pub fn exec(&self, service: &str, args: &[String]) -> Box<process::Command> {
+    let mut cmd = self.make("exec");
+
+    cmd.arg(service).args(args);
+
+    return cmd;
+  }
+
+  pub fn logs(&self, services: &[String], follow: bool) -> Box<process::Command> {
+    let mut cmd = self.make("logs");
+
+    if follow {
+      cmd.arg("-f");
+    }
+
+    cmd.args(services);
+
+    return cmd;
+  }
+
   fn make(&self, name: &str) -> Box<process::Command> {
-    let mut cmd = process::Command::new("docker");
+    let mut cmd = self.engine.compose();
 
-    cmd.arg("compose").arg(name);
-    cmd.arg("-p").arg(&self.project_name);
-    cmd.arg("--project-directory").arg(self.project_directory.as_os_str());
+    self.engine.set_project(&mut cmd, &self.project_name, &self.project_directory);
 
     self.files.iter().for_each(|f| {
       cmd.arg("-f").arg(f.as_os_str());
     });
 
     if let Some(parallel) = self.parallel {
-      cmd.arg("--parallel").arg(parallel.to_string());
+      self.engine.set_parallel(&mut cmd, parallel);
     }
 
+    cmd.arg(name);
+
     return Box::new(cmd);
   }
 }
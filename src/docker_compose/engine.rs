@@ -0,0 +1,240 @@
+use crate::config;
+use ipnet::{Ipv4Net, Ipv6Net};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process;
+
+/// Container engine selected by `engine.kind` in Hills.toml.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+  /// `docker compose`
+  Docker,
+  /// The legacy `docker-compose`
+  DockerCompose,
+  /// `podman compose`
+  Podman,
+  /// `nerdctl compose`
+  Nerdctl,
+}
+
+pub trait Engine {
+  /// Program to handle containers and networks.
+  fn cli(&self) -> &str;
+
+  fn compose(&self) -> process::Command {
+    let mut cmd = process::Command::new(self.cli());
+
+    cmd.arg("compose");
+
+    return cmd;
+  }
+
+  fn set_project(&self, cmd: &mut process::Command, name: &str, dir: &Path) {
+    cmd.arg("-p").arg(name);
+    cmd.arg("--project-directory").arg(dir.as_os_str());
+  }
+
+  /// Limit the number of the parallel operations if the engine supports it.
+  fn set_parallel(&self, _cmd: &mut process::Command, _parallel: i32) {}
+
+  fn network_exists(&self, name: &str) -> bool {
+    return process::Command::new(self.cli())
+      .arg("network")
+      .arg("inspect")
+      .arg(name)
+      .stdout(process::Stdio::null())
+      .stderr(process::Stdio::null())
+      .status()
+      .expect(&format!("Failed to execute {} network inspect", self.cli()))
+      .success();
+  }
+
+  /// Whether any container of the compose project is running.
+  fn is_running(&self, project: &str) -> bool {
+    let output = process::Command::new(self.cli())
+      .arg("ps")
+      .arg("--quiet")
+      .arg("--filter")
+      .arg(format!("label=com.docker.compose.project={}", project))
+      .output()
+      .expect(&format!("Failed to execute {} ps", self.cli()));
+
+    return output.status.success() && !output.stdout.is_empty();
+  }
+
+  /// Chain of iptables that the forwarded packets between the containers pass. Only docker creates DOCKER-USER for the users.
+  fn filter_chain(&self) -> &str {
+    return "FORWARD";
+  }
+
+  /// Create the network with IPv6 enabled if the IPv6 subnet is given.
+  fn network_create(&self, name: &str, subnet: Option<&Ipv4Net>, subnet6: Option<&Ipv6Net>) -> process::Command {
+    let mut cmd = process::Command::new(self.cli());
+
+    cmd.arg("network").arg("create");
+    add_subnets(&mut cmd, subnet, subnet6);
+    cmd.arg(name);
+
+    return cmd;
+  }
+}
+
+pub struct Docker {}
+
+pub struct DockerCompose {}
+
+pub struct Podman {}
+
+pub struct Nerdctl {}
+
+pub fn current() -> Box<dyn Engine> {
+  return new(config::current().engine_kind());
+}
+
+pub fn new(kind: Kind) -> Box<dyn Engine> {
+  return match kind {
+    Kind::Docker => Box::new(Docker {}),
+    Kind::DockerCompose => Box::new(DockerCompose {}),
+    Kind::Podman => Box::new(Podman {}),
+    Kind::Nerdctl => Box::new(Nerdctl {}),
+  };
+}
+
+impl Engine for Docker {
+  fn cli(&self) -> &str {
+    return "docker";
+  }
+
+  fn set_parallel(&self, cmd: &mut process::Command, parallel: i32) {
+    cmd.arg("--parallel").arg(parallel.to_string());
+  }
+
+  fn filter_chain(&self) -> &str {
+    return "DOCKER-USER";
+  }
+
+  fn network_create(&self, name: &str, subnet: Option<&Ipv4Net>, subnet6: Option<&Ipv6Net>) -> process::Command {
+    let mut cmd = process::Command::new(self.cli());
+
+    cmd.arg("network").arg("create").arg("--driver").arg("bridge");
+    add_subnets(&mut cmd, subnet, subnet6);
+
+    // IPv6-only network is supported since Docker 28.
+    if subnet.is_none() {
+      cmd.arg("--ipv4=false");
+    }
+
+    cmd.arg(name);
+
+    return cmd;
+  }
+}
+
+impl Engine for DockerCompose {
+  fn cli(&self) -> &str {
+    return "docker";
+  }
+
+  fn compose(&self) -> process::Command {
+    return process::Command::new("docker-compose");
+  }
+
+  fn set_parallel(&self, cmd: &mut process::Command, parallel: i32) {
+    // https://docs.docker.com/compose/environment-variables/envvars/#compose_parallel_limit
+    cmd.env("COMPOSE_PARALLEL_LIMIT", parallel.to_string());
+  }
+
+  fn filter_chain(&self) -> &str {
+    return "DOCKER-USER";
+  }
+}
+
+impl Engine for Podman {
+  fn cli(&self) -> &str {
+    return "podman";
+  }
+
+  fn set_project(&self, cmd: &mut process::Command, name: &str, dir: &Path) {
+    // podman-compose doesn't have --project-directory.
+    cmd.arg("-p").arg(name);
+    cmd.current_dir(dir);
+  }
+}
+
+impl Engine for Nerdctl {
+  fn cli(&self) -> &str {
+    return "nerdctl";
+  }
+}
+
+fn add_subnets(cmd: &mut process::Command, subnet: Option<&Ipv4Net>, subnet6: Option<&Ipv6Net>) {
+  if let Some(s) = subnet {
+    cmd.arg("--subnet").arg(s.to_string());
+  }
+
+  if let Some(s) = subnet6 {
+    cmd.arg("--ipv6").arg("--subnet").arg(s.to_string());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn args_of(cmd: &process::Command) -> Vec<String> {
+    return cmd.get_args().map(|a| a.to_str().unwrap().to_string()).collect();
+  }
+
+  #[test]
+  fn parallel_is_handled_by_each_engine() {
+    let mut docker = new(Kind::Docker).compose();
+    new(Kind::Docker).set_parallel(&mut docker, 2);
+    assert_eq!(args_of(&docker), vec!["compose", "--parallel", "2"]);
+
+    let mut legacy = new(Kind::DockerCompose).compose();
+    new(Kind::DockerCompose).set_parallel(&mut legacy, 2);
+    assert_eq!(legacy.get_program(), "docker-compose");
+    assert!(args_of(&legacy).is_empty());
+    assert_eq!(legacy.get_envs().collect::<Vec<_>>(), vec![("COMPOSE_PARALLEL_LIMIT".as_ref(), Some("2".as_ref()))]);
+
+    let mut nerdctl = new(Kind::Nerdctl).compose();
+    new(Kind::Nerdctl).set_parallel(&mut nerdctl, 2);
+    assert_eq!(args_of(&nerdctl), vec!["compose"]);
+  }
+
+  #[test]
+  fn only_docker_has_the_chain_for_the_users() {
+    assert_eq!(new(Kind::Docker).filter_chain(), "DOCKER-USER");
+    assert_eq!(new(Kind::DockerCompose).filter_chain(), "DOCKER-USER");
+    assert_eq!(new(Kind::Podman).filter_chain(), "FORWARD");
+    assert_eq!(new(Kind::Nerdctl).filter_chain(), "FORWARD");
+  }
+
+  #[test]
+  fn podman_runs_in_project_directory() {
+    let engine = new(Kind::Podman);
+    let mut cmd = engine.compose();
+    engine.set_project(&mut cmd, "app", Path::new("/tmp/app"));
+
+    assert_eq!(cmd.get_program(), "podman");
+    assert_eq!(args_of(&cmd), vec!["compose", "-p", "app"]);
+    assert_eq!(cmd.get_current_dir(), Some(Path::new("/tmp/app")));
+  }
+
+  #[test]
+  fn network_is_created_with_ipv6_subnet() {
+    let subnet: Ipv4Net = "172.31.0.0/16".parse().unwrap();
+    let subnet6: Ipv6Net = "fd00:686c::/48".parse().unwrap();
+
+    let cmd = new(Kind::Docker).network_create("hills", Some(&subnet), Some(&subnet6));
+    assert_eq!(args_of(&cmd), vec!["network", "create", "--driver", "bridge", "--subnet", "172.31.0.0/16", "--ipv6", "--subnet", "fd00:686c::/48", "hills"]);
+
+    let cmd = new(Kind::Docker).network_create("hills", None, Some(&subnet6));
+    assert_eq!(args_of(&cmd), vec!["network", "create", "--driver", "bridge", "--ipv6", "--subnet", "fd00:686c::/48", "--ipv4=false", "hills"]);
+
+    let cmd = new(Kind::Podman).network_create("hills", Some(&subnet), None);
+    assert_eq!(args_of(&cmd), vec!["network", "create", "--subnet", "172.31.0.0/16", "hills"]);
+  }
+}
@@ -0,0 +1,145 @@
+use crate::application::{self, LimitsValues};
+use crate::config;
+use crate::vm::{self, Capacity};
+use std::collections::BTreeMap;
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Resources reserved by the limits of the services.
+#[derive(Debug, Default, PartialEq)]
+pub struct Usage {
+  pub cpus: f64,
+  /// Memory in bytes.
+  pub memory: u64,
+  /// Services that have no limit of either cpus or mem_limit. They can use all the resources of the VM.
+  pub unlimited: Vec<String>,
+}
+
+pub fn usage_of(limits: &BTreeMap<String, LimitsValues>) -> Usage {
+  let mut usage = Usage::default();
+
+  for (service, l) in limits {
+    usage.cpus += l.cpus.unwrap_or_default();
+    usage.memory += l.mem_limit.as_deref().and_then(config::bytes_of).unwrap_or_default();
+
+    if l.cpus.is_none() || l.mem_limit.is_none() {
+      usage.unlimited.push(service.clone());
+    }
+  }
+
+  return usage;
+}
+
+/// Problems of the resources of the running applications against the VM. The services without the limits aren't counted.
+pub fn warnings_of(capacity: Option<Capacity>, usages: &BTreeMap<String, Usage>) -> Vec<String> {
+  let mut warnings: Vec<String> = vec![];
+  let cpus = usages.values().map(|u| u.cpus).sum::<f64>();
+  let memory = usages.values().map(|u| u.memory).sum::<u64>();
+
+  if let Some(c) = capacity {
+    if cpus > f64::from(c.cpus) {
+      warnings.push(format!("The total cpus {} of the running applications exceeds {} of the VM", cpus, c.cpus));
+    }
+
+    if memory > c.memory {
+      warnings.push(format!("The total mem_limit {} of the running applications exceeds {} of the VM", gib(memory), gib(c.memory)));
+    }
+  }
+
+  return warnings;
+}
+
+/// Services that can use all the resources of the VM. They're informed but not the problems.
+pub fn notes_of(usages: &BTreeMap<String, Usage>) -> Vec<String> {
+  return usages
+    .iter()
+    .filter(|(_, u)| !u.unlimited.is_empty())
+    .map(|(name, usage)| format!("{} of {} have no cpus or mem_limit. Set them in [resources] of the application", usage.unlimited.join(", "), name))
+    .collect();
+}
+
+fn gib(bytes: u64) -> String {
+  return format!("{:.1}GiB", bytes as f64 / GIB);
+}
+
+/// Print the resources of the VM and the running applications. It returns false if they exceed the VM. The invalid applications are skipped.
+pub fn run() -> bool {
+  let capacity = vm::current().capacity();
+  let mut usages: BTreeMap<String, Usage> = BTreeMap::new();
+
+  match capacity {
+    Some(c) => println!("VM : {} cpus, {}", c.cpus, gib(c.memory)),
+    None => println!("VM : none. The containers run on the host"),
+  }
+
+  for name in config::current().application_names() {
+    let app = match application::load(&name) {
+      Ok(app) => app,
+      Err(e) => {
+        eprintln!("Skipped {} : {}", name, e);
+        continue;
+      }
+    };
+
+    if !app.is_running() {
+      continue;
+    }
+
+    let usage = usage_of(&app.limits());
+
+    println!("{} : {} cpus, {}", name, usage.cpus, gib(usage.memory));
+    usages.insert(name, usage);
+  }
+
+  if usages.is_empty() {
+    println!("No application is running");
+  }
+
+  for n in notes_of(&usages) {
+    println!("Note: {}", n);
+  }
+
+  let warnings = warnings_of(capacity, &usages);
+
+  for w in warnings.iter() {
+    eprintln!("Warning: {}", w);
+  }
+
+  return warnings.is_empty();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn limits(cpus: Option<f64>, mem_limit: Option<&str>) -> LimitsValues {
+    return LimitsValues {
+      cpus: cpus,
+      mem_limit: mem_limit.map(|m| m.to_string()),
+      pids_limit: None,
+    };
+  }
+
+  #[test]
+  fn the_total_is_compared_with_the_vm() {
+    let web = usage_of(&BTreeMap::from([("app".to_string(), limits(Some(1.5), Some("6g"))), ("worker".to_string(), limits(Some(0.5), None))]));
+
+    assert_eq!(web.cpus, 2.0);
+    assert_eq!(web.memory, 6 * 1024 * 1024 * 1024);
+    assert_eq!(web.unlimited, vec!["worker".to_string()]);
+
+    let shared = usage_of(&BTreeMap::from([("postgres".to_string(), limits(Some(1.0), Some("2.5GiB")))]));
+    let usages = BTreeMap::from([("shared".to_string(), shared), ("web".to_string(), web)]);
+    let capacity = Capacity { cpus: 2, memory: 8 * 1024 * 1024 * 1024 };
+
+    assert_eq!(
+      warnings_of(Some(capacity), &usages),
+      vec![
+        "The total cpus 3 of the running applications exceeds 2 of the VM".to_string(),
+        "The total mem_limit 8.5GiB of the running applications exceeds 8.0GiB of the VM".to_string(),
+      ]
+    );
+    assert_eq!(notes_of(&usages), vec!["worker of web have no cpus or mem_limit. Set them in [resources] of the application".to_string()]);
+    assert_eq!(warnings_of(None, &BTreeMap::new()), Vec::<String>::new());
+  }
+}
@@ -0,0 +1,83 @@
+use crate::resolver;
+use std::fs;
+use std::io::ErrorKind;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Render the entries with the format of /etc/hosts.
+pub fn render(entries: &[(IpAddr, String)]) -> String {
+  return entries.iter().map(|(addr, name)| format!("{}\t{}\n", addr, name)).collect::<Vec<String>>().join("");
+}
+
+/// Replace the block between the marker lines in the file. The block is appended if it's not found.
+/// The file is written with sudo if the user can't write it. (e.g. /etc/hosts)
+pub fn update_block(file: &Path, marker: &str, block: &str) {
+  let original = if file.exists() { fs::read_to_string(file).expect(&format!("Failed to read {:?}", file)) } else { "".to_string() };
+  let content = replace_block(&original, marker, block);
+
+  match fs::write(file, &content) {
+    Ok(_) => {}
+    Err(e) if e.kind() == ErrorKind::PermissionDenied => resolver::sudo(&["tee", file.to_str().unwrap()], Some(&content)),
+    Err(e) => panic!("Failed to write {:?} : {}", file, e),
+  }
+
+  println!("Saved {:?}", file);
+}
+
+fn replace_block(original: &str, marker: &str, block: &str) -> String {
+  let begin = format!("# BEGIN {}", marker);
+  let end = format!("# END {}", marker);
+  let mut lines: Vec<&str> = vec![];
+  let mut inside = false;
+  let mut replaced = false;
+
+  for line in original.lines() {
+    if line.trim() == begin {
+      inside = true;
+      continue;
+    }
+
+    if inside {
+      if line.trim() == end {
+        inside = false;
+
+        if !replaced {
+          lines.push(&begin);
+          lines.extend(block.lines());
+          lines.push(&end);
+          replaced = true;
+        }
+      }
+
+      continue;
+    }
+
+    lines.push(line);
+  }
+
+  if inside {
+    panic!("Not found `{}` after `{}`", end, begin);
+  }
+
+  if !replaced {
+    lines.push(&begin);
+    lines.extend(block.lines());
+    lines.push(&end);
+  }
+
+  return format!("{}\n", lines.join("\n"));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn only_the_marked_block_is_replaced() {
+    let original = "127.0.0.1\tlocalhost\n# BEGIN hills\n172.31.1.1\told.app.local\n# END hills\n::1\tlocalhost\n";
+    let block = render(&[(IpAddr::V4("172.31.1.1".parse().unwrap()), "web.app.local".to_string())]);
+
+    assert_eq!(replace_block(original, "hills", &block), "127.0.0.1\tlocalhost\n# BEGIN hills\n172.31.1.1\tweb.app.local\n# END hills\n::1\tlocalhost\n");
+    assert_eq!(replace_block("127.0.0.1\tlocalhost\n", "hills", &block), "127.0.0.1\tlocalhost\n# BEGIN hills\n172.31.1.1\tweb.app.local\n# END hills\n");
+  }
+}
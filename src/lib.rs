@@ -1,8 +1,16 @@
 pub mod application;
+pub mod colima;
 pub mod config;
 pub mod dhcp;
 pub mod dns;
 pub mod docker_compose;
+pub mod doctor;
+pub mod hosts;
 pub mod lima;
+pub mod migration;
 pub mod mutagen;
+pub mod policy;
+pub mod resolver;
+pub mod secret;
 pub mod vm;
+pub mod watcher;
@@ -1,8 +1,16 @@
+mod command;
+pub mod image;
 mod network;
-mod provision;
+pub mod provision;
 mod template;
 
 use crate::config;
+use crate::vm::{Backend, Capacity, Status};
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
 use std::fs;
 use std::path::PathBuf;
 
@@ -10,48 +18,102 @@ pub struct Lima {
   path: String,
   os: String,
   name: String,
+  vm_type: VmType,
   cpus: u32,
   memory: String,
   disk: String,
   ssh_port: u32,
+  provision: provision::Settings,
+  mounts: Vec<template::Mount>,
+  port_forwards: Vec<template::PortForward>,
+  env: BTreeMap<String, String>,
+  images: image::Images,
 }
 
-pub fn new(path: String, cpus: u32, memory: String, disk: String, ssh_port: u32) -> Lima {
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum VmType {
+  Qemu,
+  Vz,
+}
+
+/// JSON Schema of the template of Lima.
+pub fn schema() -> RootSchema {
+  return template::schema();
+}
+
+pub fn new(path: String, vm_type: VmType, cpus: u32, memory: String, disk: String, ssh_port: u32, provision: provision::Settings) -> Lima {
   return Lima {
     path: path,
     os: "Linux".to_string(),
     name: "hills".to_string(),
+    vm_type: vm_type,
     cpus: cpus,
     memory: memory,
     disk: disk,
     ssh_port: ssh_port,
+    provision: provision,
+    mounts: vec![],
+    port_forwards: vec![],
+    env: BTreeMap::new(),
+    images: image::new("ubuntu".to_string(), "22.04".to_string(), vec![]),
   };
 }
 
+impl VmType {
+  pub fn as_str(&self) -> &str {
+    return match self {
+      VmType::Qemu => "qemu",
+      VmType::Vz => "vz",
+    };
+  }
+}
+
 impl Lima {
   pub fn os(&self) -> String {
     return self.os.clone();
   }
 
-  pub fn name(&self) -> String {
-    return format!("{}-lima", self.name.as_str());
+  /// OpenSSH config to log in to the VM. The host is the same name that mutagen uses.
+  pub fn ssh_config(&self) -> String {
+    let user = env::var("USER").expect("Not found USER in the environment");
+    let identity = dirs::home_dir().expect("Not found the home directory").join(".lima").join("_config").join("user");
+
+    return format!(
+      "Host {}\n  HostName 127.0.0.1\n  Port {}\n  User {}\n  IdentityFile {}\n  StrictHostKeyChecking no\n  UserKnownHostsFile /dev/null\n  ForwardAgent yes\n",
+      self.name,
+      self.ssh_port,
+      user,
+      identity.display()
+    );
   }
 
-  pub fn start(&self) {
-    template::ensure(&self);
-    self.update_file();
+  pub fn add_mount(&mut self, location: String, mount_point: Option<String>, writable: bool) -> &mut Self {
+    self.mounts.push(template::mount(location, mount_point, writable));
+    return self;
   }
 
-  fn update_file(&self) {
-    let mut yaml = template::load(&self);
-    let file = self.file_path();
+  pub fn add_port_forward(&mut self, guest_port: u32, host_port: u32) -> &mut Self {
+    self.port_forwards.push(template::port_forward(guest_port, host_port));
+    return self;
+  }
+
+  pub fn set_env(&mut self, key: String, value: String) -> &mut Self {
+    self.env.insert(key, value);
+    return self;
+  }
+
+  pub fn set_images(&mut self, images: image::Images) -> &mut Self {
+    self.images = images;
+    return self;
+  }
 
-    yaml.cpus = self.cpus.clone();
-    yaml.memory = self.memory.clone();
-    yaml.disk = self.disk.clone();
-    yaml.ssh.localPort = self.ssh_port.clone();
+  pub fn images(&self) -> &image::Images {
+    return &self.images;
+  }
 
-    config::create_file(file, serde_yaml::to_string(&yaml).unwrap());
+  fn update_file(&self) {
+    config::create_file(self.file_path(), template::render(self));
   }
 
   fn file_path(&self) -> Box<PathBuf> {
@@ -59,7 +121,7 @@ impl Lima {
   }
 
   fn root(&self) -> Box<PathBuf> {
-    let dir = config::current().root().join(self.path);
+    let dir = config::current().root().join(&self.path);
 
     if !dir.exists() {
       fs::create_dir_all(dir.clone()).expect(&format!("Failed to create {:?}", dir));
@@ -67,4 +129,70 @@ impl Lima {
 
     return Box::new(dir);
   }
+
+  fn command(&self) -> command::Command {
+    return command::new(self.name());
+  }
+}
+
+impl Backend for Lima {
+  fn name(&self) -> String {
+    return self.name.clone();
+  }
+
+  fn hostname(&self) -> String {
+    return format!("lima-{}", self.name);
+  }
+
+  fn requires_guest(&self) -> bool {
+    return true;
+  }
+
+  fn render_config(&self) {
+    template::ensure(self);
+    self.update_file();
+  }
+
+  fn start(&self) {
+    let cmd = self.command();
+    let ok = match cmd.status() {
+      Status::NotCreated => cmd.create(self.file_path()),
+      _ => cmd.start(),
+    };
+
+    if !ok {
+      panic!("Failed to start the lima instance {}", self.name);
+    }
+  }
+
+  fn stop(&self) {
+    if !self.command().stop() {
+      panic!("Failed to stop the lima instance {}", self.name);
+    }
+  }
+
+  fn delete(&self) {
+    if !self.command().delete() {
+      panic!("Failed to delete the lima instance {}", self.name);
+    }
+  }
+
+  fn status(&self) -> Status {
+    return self.command().status();
+  }
+
+  fn exec(&self, args: &[String]) -> bool {
+    return self.command().shell(args);
+  }
+
+  fn ssh(&self) {
+    self.command().shell(&[]);
+  }
+
+  fn capacity(&self) -> Option<Capacity> {
+    return Some(Capacity {
+      cpus: self.cpus,
+      memory: config::bytes_of(&self.memory).expect(&format!("`{}` is not a size", self.memory)),
+    });
+  }
 }
@@ -1,3 +1,4 @@
+use crate::vm::Status;
 use std::path::PathBuf;
 use std::process;
 
@@ -5,20 +6,13 @@ pub struct Command {
   name: String,
 }
 
-pub enum Status {
-  NotCreated,
-  PowerOff,
-  Aborted,
-  Running,
-}
-
 pub fn new(name: String) -> Command {
   return Command { name: name };
 }
 
 impl Command {
   pub fn status(&self) -> Status {
-    let statuses = process::Command::new("limactl")
+    let output = process::Command::new("limactl")
       .arg("list")
       .arg("-f")
       .arg("{{.Name}},{{.Status}}")
@@ -27,41 +21,47 @@ impl Command {
       .stdout
       .iter()
       .map(|&x| x as char)
-      .collect::<String>()
-      .trim()
-      .split("\n")
-      .collect::<Vec<&str>>();
+      .collect::<String>();
 
-    let status = statuses.find(|s| s.starts_with(&format!("{},", self.name)));
+    let status = output.trim().split('\n').find(|s| s.starts_with(&format!("{},", self.name)));
 
     return match status {
       Some(s) => {
-        let parts = s.split(",").collect::<Vec<&str>>();
+        let parts = s.split(',').collect::<Vec<&str>>();
 
-        match parts[1] {
+        match parts[1].to_lowercase().as_str() {
           "not_created" => Status::NotCreated,
-          "poweroff" => Status::PowerOff,
-          "aborted" => Status::Aborted,
+          "poweroff" | "stopped" => Status::PowerOff,
+          "aborted" | "broken" => Status::Aborted,
           "running" => Status::Running,
-          _ => panic!("Unknown status : {}", parts[1]),
+          _ => Status::Unknown,
         }
       }
       None => Status::NotCreated,
     };
   }
 
-  pub fn start(&self, file: Box<PathBuf>) -> bool {
-    let output = process::Command::new("limactl")
-      .arg("start")
-      .arg("--name")
-      .arg(&self.name)
-      .arg(file.as_os_str())
-      .output()
-      .expect("Failed to execute limactl start");
+  pub fn create(&self, file: Box<PathBuf>) -> bool {
+    return self.run(process::Command::new("limactl").arg("start").arg("--tty=false").arg("--name").arg(&self.name).arg(file.as_os_str()));
+  }
 
-    println!("{:?}", output.stdout);
-    eprintln!("{:?}", output.stderr);
+  pub fn start(&self) -> bool {
+    return self.run(process::Command::new("limactl").arg("start").arg(&self.name));
+  }
+
+  pub fn stop(&self) -> bool {
+    return self.run(process::Command::new("limactl").arg("stop").arg(&self.name));
+  }
+
+  pub fn delete(&self) -> bool {
+    return self.run(process::Command::new("limactl").arg("delete").arg(&self.name));
+  }
+
+  pub fn shell(&self, args: &[String]) -> bool {
+    return self.run(process::Command::new("limactl").arg("shell").arg(&self.name).args(args));
+  }
 
-    return output.status.success();
+  fn run(&self, cmd: &mut process::Command) -> bool {
+    return cmd.status().expect(&format!("Failed to execute {:?}", cmd)).success();
   }
 }
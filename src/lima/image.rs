@@ -0,0 +1,274 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Names of the architectures accepted by `normalize_arch`.
+pub static ARCH: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(x86_64|amd64|arm64|aarch64)$").unwrap());
+
+/// Image entry of the lima template.
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Image {
+  pub location: String,
+  pub arch: String,
+  pub digest: Option<String>,
+}
+
+/// Source of the image for the architecture. The location is a URL or a local file path.
+#[derive(Debug, Clone)]
+pub struct Source {
+  pub arch: String,
+  pub location: String,
+  /// Formatted with `sha256:<hex>`.
+  pub digest: Option<String>,
+}
+
+pub struct Images {
+  distro: String,
+  version: String,
+  sources: Vec<Source>,
+}
+
+pub fn new(distro: String, version: String, sources: Vec<Source>) -> Images {
+  return Images {
+    distro: distro,
+    version: version,
+    sources: sources,
+  };
+}
+
+/// Architecture of the host in the format of lima.
+pub fn arch() -> String {
+  let value = process::Command::new("uname")
+    .arg("-m")
+    .output()
+    .expect("Failed to execute uname")
+    .stdout
+    .iter()
+    .map(|&x| x as char)
+    .collect::<String>()
+    .trim()
+    .to_string();
+
+  return normalize_arch(&value).unwrap_or_else(|e| panic!("{}", e));
+}
+
+/// Architecture in the format of lima. It's also the parser of `--arch`.
+pub fn normalize_arch(value: &str) -> Result<String, String> {
+  return match value {
+    "x86_64" | "amd64" => Ok("x86_64".to_string()),
+    // macOS returns arm64 and Linux returns aarch64.
+    "arm64" | "aarch64" => Ok("aarch64".to_string()),
+    _ => Err(format!("Unsupported architecture `{}`. It has to be x86_64, amd64, arm64 or aarch64", value)),
+  };
+}
+
+impl Images {
+  pub fn source_for(&self, arch: &str) -> Source {
+    if let Some(s) = self.sources.iter().find(|s| normalize_arch(&s.arch).is_ok_and(|a| a == arch)) {
+      return s.clone();
+    }
+
+    return Source {
+      arch: arch.to_string(),
+      location: self.default_url_for(arch),
+      digest: None,
+    };
+  }
+
+  /// Image used by the VM. The cached file is used if it's fetched.
+  pub fn image_for(&self, arch: &str) -> Image {
+    let source = self.source_for(arch);
+    let cache = cache_path_of(&source);
+    let location = if cache.exists() { cache.to_str().unwrap().to_string() } else { source.location.clone() };
+
+    return Image {
+      location: location,
+      arch: arch.to_string(),
+      digest: source.digest.clone(),
+    };
+  }
+
+  /// Download the image into the cache directory and verify it.
+  pub fn fetch(&self, arch: &str) -> Box<PathBuf> {
+    let source = self.source_for(arch);
+    let cache = cache_path_of(&source);
+    let dir = cache.parent().unwrap();
+
+    if !dir.exists() {
+      fs::create_dir_all(dir).expect(&format!("Failed to create {:?}", dir));
+    }
+
+    match local_path_of(&source.location) {
+      Some(path) => {
+        fs::copy(&path, &cache).expect(&format!("Failed to copy {:?} to {:?}", path, cache));
+      }
+      None => {
+        let tmp = cache.with_extension("part");
+        let ok = process::Command::new("curl")
+          .arg("-fL")
+          .arg("-o")
+          .arg(tmp.as_os_str())
+          .arg(&source.location)
+          .status()
+          .expect("Failed to execute curl")
+          .success();
+
+        if !ok {
+          panic!("Failed to download {}", source.location);
+        }
+
+        fs::rename(&tmp, &cache).expect(&format!("Failed to move {:?} to {:?}", tmp, cache));
+      }
+    }
+
+    if !self.verify(arch) {
+      fs::remove_file(&cache).expect(&format!("Failed to remove {:?}", cache));
+      panic!("Digest mismatch for {}", source.location);
+    }
+
+    println!("Saved {:?}", cache);
+
+    return Box::new(cache);
+  }
+
+  /// Whether the cached image matches the digest. It's true if no digest is configured, and false if the image isn't cached.
+  pub fn verify(&self, arch: &str) -> bool {
+    let source = self.source_for(arch);
+    let cache = cache_path_of(&source);
+
+    if !cache.exists() {
+      eprintln!("Not found the cached image {:?}. Run `hills vm image fetch`", cache);
+      return false;
+    }
+
+    let Some(digest) = &source.digest else {
+      return true;
+    };
+
+    return match sha256_of(&cache) {
+      Ok(hex) => digest.trim_start_matches("sha256:").eq_ignore_ascii_case(&hex),
+      Err(e) => {
+        eprintln!("{}", e);
+        false
+      }
+    };
+  }
+
+  fn default_url_for(&self, arch: &str) -> String {
+    let suffix = match arch {
+      "x86_64" => "amd64",
+      "aarch64" => "arm64",
+      _ => panic!("Unsupported architecture: {}", arch),
+    };
+
+    return match self.distro.as_str() {
+      "ubuntu" => format!("https://cloud-images.ubuntu.com/releases/{0}/release/ubuntu-{0}-server-cloudimg-{1}.img", self.version, suffix),
+      "debian" => format!("https://cloud.debian.org/images/cloud/{0}/latest/debian-{1}-genericcloud-{2}.qcow2", codename_of_debian(&self.version), self.version, suffix),
+      _ => panic!("Unsupported distro without sources: {}", self.distro),
+    };
+  }
+}
+
+fn codename_of_debian(version: &str) -> &str {
+  return match version {
+    "11" => "bullseye",
+    "12" => "bookworm",
+    "13" => "trixie",
+    _ => panic!("Unsupported debian version: {}", version),
+  };
+}
+
+/// Path of the cached image. It's keyed by the hash of the location so that the images of the same file name don't collide.
+fn cache_path_of(source: &Source) -> PathBuf {
+  let hash = format!("{:x}", Sha256::digest(source.location.as_bytes()));
+  let name = format!("{}-{}", &hash[..16], source.location.rsplit('/').next().unwrap());
+
+  return dirs::cache_dir().expect("Not found the cache directory").join("hills").join("images").join(name);
+}
+
+fn local_path_of(location: &str) -> Option<PathBuf> {
+  if let Some(path) = location.strip_prefix("file://") {
+    return Some(PathBuf::from(path));
+  }
+
+  if location.contains("://") {
+    return None;
+  }
+
+  if let Some(path) = location.strip_prefix("~/") {
+    return Some(dirs::home_dir().expect("Not found the home directory").join(path));
+  }
+
+  return Some(PathBuf::from(location));
+}
+
+fn sha256_of(path: &Path) -> Result<String, String> {
+  let mut file = fs::File::open(path).map_err(|e| format!("Failed to open {:?} : {}", path, e))?;
+  let mut hasher = Sha256::new();
+
+  io::copy(&mut file, &mut hasher).map_err(|e| format!("Failed to read {:?} : {}", path, e))?;
+
+  return Ok(format!("{:x}", hasher.finalize()));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn linux_aarch64_is_supported() {
+    assert_eq!(normalize_arch("aarch64"), Ok("aarch64".to_string()));
+    assert_eq!(normalize_arch("arm64"), Ok("aarch64".to_string()));
+    assert_eq!(normalize_arch("x86_64"), Ok("x86_64".to_string()));
+    assert!(normalize_arch("riscv64").is_err());
+  }
+
+  #[test]
+  fn source_falls_back_to_the_distro_url() {
+    let images = new(
+      "ubuntu".to_string(),
+      "24.04".to_string(),
+      vec![Source {
+        arch: "arm64".to_string(),
+        location: "/srv/images/ubuntu-arm64.img".to_string(),
+        digest: Some("sha256:abc".to_string()),
+      }],
+    );
+
+    assert_eq!(images.source_for("aarch64").location, "/srv/images/ubuntu-arm64.img");
+    assert_eq!(images.source_for("x86_64").location, "https://cloud-images.ubuntu.com/releases/24.04/release/ubuntu-24.04-server-cloudimg-amd64.img");
+  }
+
+  #[test]
+  fn images_are_cached_by_the_location() {
+    let source = |location: &str| Source {
+      arch: "aarch64".to_string(),
+      location: location.to_string(),
+      digest: None,
+    };
+    let a = cache_path_of(&source("https://a.example.com/images/disk.img"));
+    let b = cache_path_of(&source("https://b.example.com/images/disk.img"));
+
+    assert_ne!(a, b);
+    assert!(a.file_name().unwrap().to_str().unwrap().ends_with("-disk.img"));
+  }
+
+  #[test]
+  fn sha256_is_computed_in_process() {
+    let dir = tempdir::TempDir::new("image").unwrap();
+    let file = dir.path().join("disk.img");
+
+    fs::write(&file, "hills").unwrap();
+
+    assert_eq!(sha256_of(&file), Ok("a926ca156b77ada4a2c4bba622a36599d678eb2571a3fdab5443134683cfbe18".to_string()));
+    assert!(sha256_of(&dir.path().join("missing.img")).is_err());
+  }
+}
@@ -1,16 +1,24 @@
+use super::VmType;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use std::process;
 
+#[allow(non_snake_case)]
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct Network {
-  socket: String,
+  socket: Option<String>,
+  vzNAT: Option<bool>,
 }
 
-pub fn new() -> Network {
-  return Network {
-    socket: format!("{}/var/run/socket_vmnet", prefix()),
+pub fn new(vm_type: VmType) -> Network {
+  return match vm_type {
+    VmType::Qemu => Network {
+      socket: Some(format!("{}/var/run/socket_vmnet", prefix())),
+      vzNAT: None,
+    },
+    VmType::Vz => Network { socket: None, vzNAT: Some(true) },
   };
 }
 
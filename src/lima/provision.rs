@@ -1,39 +1,59 @@
+use crate::docker_compose::engine;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use std::fs;
 use std::path::PathBuf;
 
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct Provision {
   mode: String,
   script: String,
 }
 
+/// Settings for the built-in provisioning.
+#[derive(Debug, Clone)]
+pub struct Settings {
+  pub timezone: String,
+  pub packages: Vec<String>,
+  pub engine: engine::Kind,
+  /// Names of the built-in scripts or destinations of the built-in files to skip.
+  pub disable: Vec<String>,
+}
+
 pub fn new(script: String, as_user: bool) -> Provision {
   let mode = if as_user { "user" } else { "system" };
 
   return Provision { mode: mode.to_string(), script: script };
 }
 
-pub fn from(file: Box<PathBuf>) -> Provision {
-  let script = fs::read_to_string(*file).expect(&format!("Failed to read {:?}", file));
+pub fn from(file: Box<PathBuf>, as_user: bool) -> Provision {
+  let script = fs::read_to_string(file.as_ref()).expect(&format!("Failed to read {:?}", file));
 
-  return Provision { mode: "system".to_string(), script: script };
+  return new(script, as_user);
 }
 
 pub fn dispatch(from: Box<PathBuf>, to: Box<PathBuf>) -> Provision {
-  let body = fs::read_to_string(*from).expect(&format!("Failed to read {:?}", from));
+  let body = fs::read(from.as_ref()).expect(&format!("Failed to read {:?}", from));
+
+  return put(&body, to);
+}
 
+/// Write the file in the VM. The body is encoded with base64 so that any content including binaries is written as it is.
+pub fn put(body: &[u8], to: Box<PathBuf>) -> Provision {
   let script = format!(
     r#"#!/bin/bash
-cat <<'EOF' > {}
-{}
-EOF
+set -eu
+mkdir -p '{}'
+echo '{}' | base64 -d > '{}'
 "#,
-    to.display(),
-    body
+    to.parent().unwrap().display(),
+    STANDARD.encode(body),
+    to.display()
   );
 
-  return Provision { mode: "system".to_string(), script: script };
+  return new(script, false);
 }
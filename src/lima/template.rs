@@ -1,61 +1,107 @@
 use crate::config;
+use crate::docker_compose::engine;
 use crate::lima;
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
+use serde_yaml::Value;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
-use std::process;
 
 use super::Lima;
 
+mod provision_files;
+
 #[allow(non_snake_case)]
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 struct Yaml {
   vmType: String,
-  pub cpus: u32,
-  pub memory: String,
-  pub disk: String,
+  cpus: u32,
+  memory: String,
+  disk: String,
   arch: String,
-  images: Vec<Image>,
+  images: Vec<lima::image::Image>,
   containerd: Containerd,
-  pub ssh: Ssh,
+  ssh: Ssh,
   provision: Option<Vec<lima::provision::Provision>>,
   portForwards: Vec<PortForward>,
   networks: Vec<lima::network::Network>,
 }
 
+/// Values managed by hills. They're merged into the template.
+#[allow(non_snake_case)]
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug)]
-struct Image {
-  location: String,
-  arch: String,
+#[derive(Serialize, Debug)]
+struct Overlay {
+  vmType: String,
+  cpus: u32,
+  memory: String,
+  disk: String,
+  images: Vec<lima::image::Image>,
+  containerd: ContainerdOverlay,
+  ssh: SshOverlay,
+  env: Option<BTreeMap<String, String>>,
 }
 
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 struct Containerd {
   system: bool,
   user: bool,
 }
 
+#[derive(Serialize, Debug)]
+struct ContainerdOverlay {
+  user: bool,
+}
+
 #[allow(non_snake_case)]
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 struct Ssh {
-  pub localPort: u32,
+  localPort: u32,
   forwardAgent: bool,
   loadDotSSHPubKeys: bool,
 }
 
+#[allow(non_snake_case)]
+#[derive(Serialize, Debug)]
+struct SshOverlay {
+  localPort: u32,
+}
+
 #[allow(non_snake_case)]
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug)]
-struct PortForward {
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct PortForward {
   guestPort: u32,
   hostPort: u32,
 }
 
+#[allow(non_snake_case)]
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Mount {
+  location: String,
+  mountPoint: Option<String>,
+  writable: Option<bool>,
+}
+
+pub fn port_forward(guest_port: u32, host_port: u32) -> PortForward {
+  return PortForward { guestPort: guest_port, hostPort: host_port };
+}
+
+pub fn mount(location: String, mount_point: Option<String>, writable: bool) -> Mount {
+  return Mount {
+    location: location,
+    mountPoint: mount_point,
+    writable: Some(writable),
+  };
+}
+
 pub fn ensure(lima: &Lima) {
   let file = path(lima);
 
@@ -64,7 +110,34 @@ pub fn ensure(lima: &Lima) {
   }
 }
 
-pub fn load(lima: &Lima) -> Yaml {
+/// Render the template with the values of the config.
+/// The keys that are not managed by hills in the template are kept as they are. The entries of the sequences are appended to the ones of the user.
+pub fn render(lima: &Lima) -> String {
+  let mut yaml = load(lima);
+  let overlay = Overlay {
+    vmType: lima.vm_type.as_str().to_string(),
+    cpus: lima.cpus,
+    memory: lima.memory.clone(),
+    disk: lima.disk.clone(),
+    images: vec![lima.images.image_for(&lima::image::arch())],
+    // nerdctl needs containerd that's run by lima.
+    containerd: ContainerdOverlay {
+      user: config::current().engine_kind() == engine::Kind::Nerdctl,
+    },
+    ssh: SshOverlay { localPort: lima.ssh_port },
+    env: if lima.env.is_empty() { None } else { Some(lima.env.clone()) },
+  };
+
+  merge(&mut yaml, serde_yaml::to_value(&overlay).unwrap());
+  append(&mut yaml, "mounts", lima.mounts.iter().map(|m| serde_yaml::to_value(m).unwrap()).collect());
+  append(&mut yaml, "portForwards", lima.port_forwards.iter().map(|p| serde_yaml::to_value(p).unwrap()).collect());
+  append(&mut yaml, "provision", provisions(lima).iter().map(|p| serde_yaml::to_value(p).unwrap()).collect());
+  append(&mut yaml, "networks", vec![serde_yaml::to_value(lima::network::new(lima.vm_type)).unwrap()]);
+
+  return serde_yaml::to_string(&yaml).unwrap();
+}
+
+fn load(lima: &Lima) -> Value {
   let file = path(lima);
   let error_message = format!("Failed to load {:?}", file);
   let s = fs::read_to_string(*file).expect(&error_message);
@@ -72,22 +145,72 @@ pub fn load(lima: &Lima) -> Yaml {
   return serde_yaml::from_str(&s).expect(&error_message);
 }
 
+/// Merge the mappings recursively. The other values of `base` are replaced by `overlay`.
+fn merge(base: &mut Value, overlay: Value) {
+  match (base, overlay) {
+    (Value::Mapping(base), Value::Mapping(overlay)) => {
+      for (k, v) in overlay {
+        match base.get_mut(&k) {
+          Some(b) => merge(b, v),
+          None => {
+            base.insert(k, v);
+          }
+        }
+      }
+    }
+    (base, overlay) => *base = overlay,
+  }
+}
+
+/// Append the values that the sequence of `key` doesn't have yet.
+fn append(base: &mut Value, key: &str, values: Vec<Value>) {
+  if values.is_empty() {
+    return;
+  }
+
+  let mapping = base.as_mapping_mut().expect("The lima template must be a mapping");
+  let seq = mapping.entry(Value::from(key)).or_insert(Value::Sequence(vec![]));
+
+  if seq.is_null() {
+    *seq = Value::Sequence(vec![]);
+  }
+
+  let seq = seq.as_sequence_mut().expect(&format!("`{}` in the lima template must be a sequence", key));
+
+  for v in values {
+    if !seq.contains(&v) {
+      seq.push(v);
+    }
+  }
+}
+
+/// Built-in provisioning merged with the one under `lima/provision`.
+pub fn provisions(lima: &Lima) -> Vec<lima::provision::Provision> {
+  return provision_files::all(&lima.provision, &lima.root().join("provision"));
+}
+
+/// JSON Schema of the keys managed by hills. The other keys of Lima are allowed.
+pub fn schema() -> RootSchema {
+  let mut schema = schema_for!(Yaml);
+
+  schema.schema.metadata().title = Some("Lima template".to_string());
+
+  return schema;
+}
+
 fn path(lima: &Lima) -> Box<PathBuf> {
   return Box::new(lima.root().join("template.yml"));
 }
 
 fn create(lima: &Lima) {
-  let arch = arch();
+  let arch = lima::image::arch();
   let yaml = Yaml {
-    vmType: "qemu".to_string(),
+    vmType: lima.vm_type.as_str().to_string(),
     cpus: 2,
     memory: "8GB".to_string(),
     disk: "30GB".to_string(),
     arch: arch.clone(),
-    images: vec![Image {
-      location: image_url_for(arch.as_str()),
-      arch: arch.clone(),
-    }],
+    images: vec![lima.images.image_for(&arch)],
     containerd: Containerd { system: false, user: false },
     ssh: Ssh {
       localPort: 2222,
@@ -96,35 +219,43 @@ fn create(lima: &Lima) {
     },
     provision: None,
     portForwards: vec![PortForward { guestPort: 53, hostPort: 53 }],
-    networks: vec![lima::network::new()],
+    networks: vec![lima::network::new(lima.vm_type)],
   };
 
   config::create_file(path(lima), serde_yaml::to_string(&yaml).unwrap());
 }
 
-fn arch() -> String {
-  let value = process::Command::new("uname")
-    .arg("-m")
-    .output()
-    .expect("Failed to execute uname")
-    .stdout
-    .iter()
-    .map(|&x| x as char)
-    .collect::<String>()
-    .trim()
-    .to_string();
-
-  return match value.as_str() {
-    "x86_64" => "x86_64".to_string(),
-    "arm64" => "aarch64".to_string(),
-    _ => panic!("Unsupported architecture: {}", value),
-  };
-}
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn merge_keeps_unknown_keys() {
+    let mut base: Value = serde_yaml::from_str("cpus: 2\nrosetta:\n  enabled: true\nssh:\n  localPort: 2222\n  forwardAgent: true\nportForwards:\n- guestPort: 53\n  hostPort: 53\n").unwrap();
+    let overlay: Value = serde_yaml::from_str("cpus: 4\nssh:\n  localPort: 60022\n").unwrap();
+
+    merge(&mut base, overlay);
+    append(&mut base, "portForwards", vec![serde_yaml::to_value(port_forward(53, 53)).unwrap(), serde_yaml::to_value(port_forward(3000, 3000)).unwrap()]);
+    append(&mut base, "mounts", vec![serde_yaml::to_value(mount("~/src".to_string(), None, true)).unwrap()]);
+
+    let expected: Value = serde_yaml::from_str(
+      "cpus: 4\nrosetta:\n  enabled: true\nssh:\n  localPort: 60022\n  forwardAgent: true\nportForwards:\n- guestPort: 53\n  hostPort: 53\n- guestPort: 3000\n  hostPort: 3000\nmounts:\n- location: ~/src\n  writable: true\n",
+    )
+    .unwrap();
+
+    assert_eq!(base, expected);
+  }
+
+  #[test]
+  fn append_keeps_the_provisions_of_the_user() {
+    let mut base: Value = serde_yaml::from_str("provision:\n- mode: user\n  script: echo mine\n").unwrap();
+    let hills = serde_yaml::to_value(lima::provision::new("echo hills".to_string(), false)).unwrap();
+
+    append(&mut base, "provision", vec![hills.clone()]);
+    append(&mut base, "provision", vec![hills]);
+
+    let expected: Value = serde_yaml::from_str("provision:\n- mode: user\n  script: echo mine\n- mode: system\n  script: echo hills\n").unwrap();
 
-fn image_url_for(arch: &str) -> String {
-  match arch {
-    "x86_64" => "https://cloud-images.ubuntu.com/releases/22.04/release/ubuntu-22.04-server-cloudimg-amd64.img".to_string(),
-    "aarch64" => "https://cloud-images.ubuntu.com/releases/22.04/release/ubuntu-22.04-server-cloudimg-arm64.img".to_string(),
-    _ => panic!("Unsupported architecture: {}", arch),
+    assert_eq!(base, expected);
   }
 }
@@ -1,69 +1,82 @@
-const FILES: BTreeMap<&str, &str> = BTreeMap::from([
-  ("01_rootable.sh", "sudo cp ~/.ssh/authorized_keys /root/.ssh/"),
-  ("10_tz.sh", "timedatectl set-timezone Asia/Tokyo"),
-  (
-    "10_ip_forwarding.sh",
-    r#"
+use crate::docker_compose::engine;
+use crate::lima::provision::{self, Provision, Settings};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Priority of the files. They're put after the basic setup and before the packages and the services that read them.
+const FILES_PRIORITY: u32 = 20;
+
+/// Priority of the scripts without the numeric prefix. They're run at the last.
+const DEFAULT_PRIORITY: u32 = 99;
+
+/// Built-in scripts and files. The name starting with `/` is the destination of the file.
+fn builtins(settings: &Settings) -> Vec<(String, String)> {
+  return vec![
+    ("01_rootable.sh".to_string(), "sudo cp ~/.ssh/authorized_keys /root/.ssh/".to_string()),
+    ("10_tz.sh".to_string(), format!("timedatectl set-timezone '{}'", settings.timezone)),
+    (
+      "10_ip_forwarding.sh".to_string(),
+      r#"
 bash -c "sed -i 's/^#net\.ipv4\.ip_forward/net.ipv4.ip_forward=1/' /etc/sysctl.conf"
 bash -c "sed -i 's/^#net\.ipv6\.conf\.all\.forwarding/net.ipv6.conf.all.forwarding=1/' /etc/sysctl.conf"
-"#,
-  ),
-  // https://github.com/moby/moby/issues/22635
-  (
-    "30_dns_server.sh",
-    r#"
+"#
+      .to_string(),
+    ),
+    // https://github.com/moby/moby/issues/22635
+    (
+      "30_dns_server.sh".to_string(),
+      r#"
 bash -c "sed -i 's/^#DNS=.*$/DNS=8.8.8.8/' /etc/systemd/resolved.conf"
-"#,
-  ),
-  // https://qiita.com/shora_kujira16/items/31d09b373809a5a44ae5
-  (
-    "30_dns_stub_listener.sh",
-    r#"
+"#
+      .to_string(),
+    ),
+    // https://qiita.com/shora_kujira16/items/31d09b373809a5a44ae5
+    (
+      "30_dns_stub_listener.sh".to_string(),
+      r#"
 bash -c "sed -i 's/^#DNSStubListener=.*$/DNSStubListener=no/' /etc/systemd/resolved.conf"
-"#,
-  ),
-  (
-    "50_apt.sh",
-    r#"
+"#
+      .to_string(),
+    ),
+    (
+      "50_apt.sh".to_string(),
+      format!(
+        r#"
 apt update
-apt -y install --no-install-recommends build-essential ruby mysql-client uidmap dbus-user-session net-tools
+apt -y install --no-install-recommends {}
 rm -rf /var/lib/apt/lists/* /var/cache/apt/*
 "#,
-  ),
-  (
-    "70_docker.sh",
-    r#"
-curl -fsSL https://get.docker.com | sh
-
-curl -L "https://github.com/docker/compose/releases/download/v2.10.0/docker-compose-$(uname -s)-$(uname -m)" -o /tmp/docker-compose
-chmod +x /tmp/docker-compose
-mv /tmp/docker-compose /usr/local/bin/docker-compose
-"#,
-  ),
-  (
-    "90_restart_service.sh",
-    r#"
+        settings.packages.join(" ")
+      ),
+    ),
+    ("70_container_engine.sh".to_string(), container_engine_script(settings.engine)),
+    (
+      "90_restart_service.sh".to_string(),
+      r#"
 sysctl --system
 
 systemctl restart multipathd.service
 
 ln -sf ../run/systemd/resolve/resolv.conf /etc/resolv.conf
 systemctl restart systemd-resolved
-"#,
-  ),
-  // https://qiita.com/yn-misaki/items/c850a07f7858437e4d26
-  // https://qiita.com/tachibanayu24/items/951b358fffeb0378ff53
-  (
-    "/etc/sysctl.d/60-inotify-limit.conf",
-    r#"
+"#
+      .to_string(),
+    ),
+    // https://qiita.com/yn-misaki/items/c850a07f7858437e4d26
+    // https://qiita.com/tachibanayu24/items/951b358fffeb0378ff53
+    (
+      "/etc/sysctl.d/60-inotify-limit.conf".to_string(),
+      r#"
 fs.inotify.max_user_watches=524288
 fs.inotify.max_user_instances=256
-"#,
-  ),
-  // https://sleeplessbeastie.eu/2021/01/06/how-to-fix-multipath-daemon-error-about-missing-path-when-using-virtualbox/
-  (
-    "/etc/multipath.conf",
-    r#"
+"#
+      .to_string(),
+    ),
+    // https://sleeplessbeastie.eu/2021/01/06/how-to-fix-multipath-daemon-error-about-missing-path-when-using-virtualbox/
+    (
+      "/etc/multipath.conf".to_string(),
+      r#"
 defaults {
     user_friendly_names yes
 }
@@ -74,26 +87,163 @@ blacklist {
         product "HARDDISK"
     }
 }
-"#,
-  ),
-  // http://itemy.net/?p=539
-  (
-    "/root/.profile",
-    r#"
+"#
+      .to_string(),
+    ),
+    // http://itemy.net/?p=539
+    (
+      "/root/.profile".to_string(),
+      r#"
 [ "$BASH" -a -f ~/.bashrc ] && . ~/.bashrc
 tty -s && mesg n
-"#,
-  ),
-]);
+"#
+      .to_string(),
+    ),
+  ];
+}
+
+fn container_engine_script(kind: engine::Kind) -> String {
+  return match kind {
+    engine::Kind::Docker => "curl -fsSL https://get.docker.com | sh".to_string(),
+    engine::Kind::DockerCompose => r#"
+curl -fsSL https://get.docker.com | sh
 
-pub fn all() -> Vec<Box<PathBuf>> {
-  return FILES.keys().map(|k| Box::new(PathBuf::from(k)) as Box<PathBuf>).collect();
+curl -L "https://github.com/docker/compose/releases/latest/download/docker-compose-$(uname -s)-$(uname -m)" -o /tmp/docker-compose
+chmod +x /tmp/docker-compose
+mv /tmp/docker-compose /usr/local/bin/docker-compose
+"#
+    .to_string(),
+    engine::Kind::Podman => "apt update && apt -y install --no-install-recommends podman podman-compose".to_string(),
+    // Lima installs nerdctl with containerd.
+    engine::Kind::Nerdctl => "".to_string(),
+  };
 }
 
-pub fn script_of(path: Box<PathBuf>) -> String {
-  return script_with(FILES[path.to_str().unwrap()]);
+/// Merge the built-in provisioning with the one under `<dir>` in the order of the priority and the name.
+///
+/// - The priority of a script is the numeric prefix of the name. (e.g. 60 of `60_ruby.sh`)
+/// - The files are put at the priority 20.
+/// - `<dir>/*` is run as a script. It's run as the user if the name ends with `.user.sh`.
+/// - `<dir>/files/**` is copied to the absolute path in the VM. (e.g. `<dir>/files/etc/foo.conf` to `/etc/foo.conf`)
+///
+/// The built-in one is replaced by the user's one that has the same name.
+pub fn all(settings: &Settings, dir: &Path) -> Vec<Provision> {
+  let mut provisions: BTreeMap<String, Provision> = BTreeMap::new();
+
+  for (name, body) in builtins(settings) {
+    if settings.disable.contains(&name) {
+      continue;
+    }
+
+    let p = if name.starts_with('/') {
+      provision::put(body.as_bytes(), Box::new(PathBuf::from(&name)))
+    } else {
+      provision::new(script_with(&body), false)
+    };
+
+    provisions.insert(name, p);
+  }
+
+  if dir.exists() {
+    for path in files_in(dir) {
+      let name = path.file_name().unwrap().to_str().unwrap().to_string();
+      let as_user = name.ends_with(".user.sh");
+      let body = fs::read_to_string(&path).expect(&format!("Failed to read {:?}", path));
+
+      let p = if body.starts_with("#!") {
+        provision::from(Box::new(path), as_user)
+      } else {
+        provision::new(script_with(&body), as_user)
+      };
+
+      provisions.insert(name, p);
+    }
+
+    let files_dir = dir.join("files");
+
+    if files_dir.exists() {
+      for path in all_files_in(&files_dir) {
+        let to = Path::new("/").join(path.strip_prefix(&files_dir).unwrap());
+
+        provisions.insert(to.to_str().unwrap().to_string(), provision::dispatch(Box::new(path), Box::new(to)));
+      }
+    }
+  }
+
+  let mut ordered = provisions.into_iter().collect::<Vec<(String, Provision)>>();
+
+  ordered.sort_by_key(|(name, _)| (priority_of(name), name.clone()));
+
+  return ordered.into_iter().map(|(_, p)| p).collect();
+}
+
+fn priority_of(name: &str) -> u32 {
+  if name.starts_with('/') {
+    return FILES_PRIORITY;
+  }
+
+  let digits = name.chars().take_while(|c| c.is_ascii_digit()).collect::<String>();
+
+  return digits.parse().unwrap_or(DEFAULT_PRIORITY);
+}
+
+fn files_in(dir: &Path) -> Vec<PathBuf> {
+  let mut list: Vec<PathBuf> = fs::read_dir(dir).expect(&format!("Failed to read {:?}", dir)).map(|e| e.unwrap().path()).filter(|p| p.is_file()).collect();
+
+  list.sort();
+
+  return list;
+}
+
+fn all_files_in(dir: &Path) -> Vec<PathBuf> {
+  let mut list: Vec<PathBuf> = vec![];
+
+  for entry in fs::read_dir(dir).expect(&format!("Failed to read {:?}", dir)) {
+    let path = entry.unwrap().path();
+
+    if path.is_dir() {
+      list.append(&mut all_files_in(&path));
+    } else {
+      list.push(path);
+    }
+  }
+
+  list.sort();
+
+  return list;
 }
 
 fn script_with(script: &str) -> String {
-  return vec!["#!/bin/bash", "set -eux -o pipefail", script].join("\n");
+  return ["#!/bin/bash", "set -eux -o pipefail", script].join("\n");
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempdir::TempDir;
+
+  #[test]
+  fn user_provisions_are_merged_in_order() {
+    let dir = TempDir::new("provision").unwrap();
+    fs::write(dir.path().join("60_ruby.sh"), "apt -y install ruby").unwrap();
+    fs::write(dir.path().join("10_tz.sh"), "#!/bin/sh\necho overridden").unwrap();
+    fs::create_dir_all(dir.path().join("files/etc/hills")).unwrap();
+    fs::write(dir.path().join("files/etc/hills/app.conf"), "key=value").unwrap();
+
+    let settings = Settings {
+      timezone: "Etc/UTC".to_string(),
+      packages: vec!["net-tools".to_string()],
+      engine: engine::Kind::Docker,
+      disable: vec!["/etc/multipath.conf".to_string(), "90_restart_service.sh".to_string()],
+    };
+    let scripts = all(&settings, dir.path()).iter().map(|p| serde_yaml::to_string(p).unwrap()).collect::<Vec<String>>();
+
+    assert_eq!(scripts.len(), 11);
+    assert!(scripts[0].contains("sudo cp ~/.ssh/authorized_keys"));
+    assert!(scripts[2].contains("echo overridden"));
+    assert!(scripts[3].contains("echo 'a2V5PXZhbHVl' | base64 -d > '/etc/hills/app.conf'"));
+    assert!(scripts[9].contains("apt -y install ruby"));
+    assert!(scripts[10].contains("get.docker.com"));
+    assert!(!scripts.iter().any(|s| s.contains("multipath") || s.contains("systemctl restart")));
+  }
 }
@@ -1,9 +1,23 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use hills::application;
+use hills::application::dotenv::Layer;
 use hills::config;
+use hills::doctor;
+use hills::hosts;
+use hills::lima::{self, image};
+use hills::migration;
+use hills::mutagen;
+use hills::policy;
+use hills::resolver;
 use hills::vm;
+use hills::watcher;
+use std::env;
 use std::fs;
-use std::path::Path;
+use std::io::{self, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -12,10 +26,9 @@ struct Cli {
   #[command(subcommand)]
   action: Actions,
 
-  /// Set root directory
+  /// Set root directory. Default is HILLS_ROOT or the nearest directory that has Hills.toml.
   #[arg(short, long)]
-  #[arg(default_value = ".")]
-  context: String,
+  context: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -26,6 +39,12 @@ enum Actions {
   /// Create a new application configuration file.
   New(NewArgs),
 
+  /// Import the local checkout that has the compose files as an application.
+  Import(ImportArgs),
+
+  /// Remove the application and everything generated for it.
+  Rm(RmArgs),
+
   /// Create an alias for the application.
   Alias(AliasArgs),
 
@@ -40,17 +59,132 @@ enum Actions {
 
   /// Handle VM.
   Vm(VmArgs),
+
+  /// Print the addresses of the services with the format of /etc/hosts.
+  Hosts(HostsArgs),
+
+  /// Print the OpenSSH config for the VM.
+  SshConfig(SshConfigArgs),
+
+  /// Make the host resolve the domain of the applications.
+  Resolver(ResolverArgs),
+
+  /// Watch the config and compose files, and regenerate the files derived from them.
+  Watch(WatchArgs),
+
+  /// Handle the environment variables of the application.
+  Env(EnvArgs),
+
+  /// Validate the application TOML.
+  Validate(ValidateArgs),
+
+  /// Handle the configuration.
+  Config(ConfigArgs),
+
+  /// Upgrade Hills.toml and the application TOMLs to the latest version.
+  Migrate(MigrateArgs),
+
+  /// Print the JSON Schema of the file for the editors. (e.g. taplo)
+  Schema(SchemaArgs),
+
+  /// Handle the network of the applications.
+  Network(NetworkArgs),
+
+  /// Check the resources of the running applications against the VM.
+  Doctor,
 }
 
 #[derive(Args, Debug)]
 struct NewArgs {
+  #[arg(required_unless_present = "list_templates")]
+  name: Option<String>,
+
+  /// Template under templates/ of the root or ~/.config/hills/templates.
+  #[arg(short, long)]
+  template: Option<String>,
+
+  /// Variable of the template. (e.g. --var ruby=3.3)
+  #[arg(long = "var", value_name = "KEY=VALUE", requires = "template")]
+  vars: Vec<String>,
+
+  /// List the templates.
+  #[arg(long, conflicts_with_all = ["name", "template"])]
+  list_templates: bool,
+}
+
+#[derive(Args, Debug)]
+struct ImportArgs {
+  #[arg(required_unless_present = "scan", conflicts_with = "scan")]
+  path: Option<PathBuf>,
+
+  /// Name of the application. Default is the directory name.
+  #[arg(long, conflicts_with = "scan")]
+  name: Option<String>,
+
+  /// Import every repository found under the directory.
+  #[arg(long)]
+  scan: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct RmArgs {
   name: String,
+
+  /// Keep the volumes of the containers.
+  #[arg(long)]
+  keep_volumes: bool,
+
+  /// Delete the repository too.
+  #[arg(long)]
+  purge_repo: bool,
+
+  /// Remove without the confirmation.
+  #[arg(short, long)]
+  yes: bool,
 }
 
 #[derive(Args, Debug)]
 struct AliasArgs {
-  original: Option<String>,
-  alias: Option<String>,
+  #[command(subcommand)]
+  action: AliasActions,
+}
+
+#[derive(Subcommand, Debug)]
+enum AliasActions {
+  /// Print the aliases with the application names.
+  Ls(AliasLsArgs),
+
+  /// Add the alias of the application. It's published as the domain too.
+  Add(AliasAddArgs),
+
+  /// Remove the alias.
+  Rm(AliasRmArgs),
+
+  /// Rename the application with the files derived from it.
+  RenameApp(AliasRenameAppArgs),
+}
+
+#[derive(Args, Debug)]
+struct AliasLsArgs {
+  /// Only the application.
+  name: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct AliasAddArgs {
+  name: String,
+  alias: String,
+}
+
+#[derive(Args, Debug)]
+struct AliasRmArgs {
+  alias: String,
+}
+
+#[derive(Args, Debug)]
+struct AliasRenameAppArgs {
+  name: String,
+  new_name: String,
 }
 
 #[derive(Args, Debug)]
@@ -60,42 +194,398 @@ struct ListArgs {
 
 #[derive(Args, Debug)]
 struct UpArgs {
-  name: String,
+  /// Default is the application of the current directory.
+  name: Option<String>,
 }
 
 #[derive(Args, Debug)]
 struct UpdateArgs {
+  /// Default is the application of the current directory.
+  name: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct HostsArgs {
+  /// Only the application.
+  #[arg(long)]
+  app: Option<String>,
+
+  /// Update the marked block in the file instead of printing.
+  #[arg(long)]
+  write: Option<PathBuf>,
+
+  /// Marker of the block in the file.
+  #[arg(long, default_value = "hills")]
+  marker: String,
+}
+
+#[derive(Args, Debug)]
+struct SshConfigArgs {
+  /// Update the marked block in the file instead of printing. (e.g. ~/.ssh/config)
+  #[arg(long)]
+  write: Option<PathBuf>,
+
+  /// Marker of the block in the file.
+  #[arg(long, default_value = "hills")]
+  marker: String,
+}
+
+#[derive(Args, Debug)]
+struct ResolverArgs {
+  #[command(subcommand)]
+  action: ResolverActions,
+
+  /// Resolver of the host. Default is resolver.kind in Hills.toml or detected from the host.
+  #[arg(long, global = true)]
+  kind: Option<resolver::Kind>,
+}
+
+#[derive(Subcommand, Debug)]
+enum ResolverActions {
+  /// Write the resolver config. It requires sudo.
+  Install(ResolverWriteArgs),
+
+  /// Remove the resolver config. It requires sudo.
+  Uninstall(ResolverWriteArgs),
+
+  /// Show whether the resolver config is installed.
+  Status,
+}
+
+#[derive(Args, Debug)]
+struct ResolverWriteArgs {
+  /// Only print the files.
+  #[arg(long)]
+  dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+struct WatchArgs {
+  /// Interval in milliseconds to check the files.
+  #[arg(long, default_value_t = 1000)]
+  interval: u64,
+
+  /// Milliseconds to wait for the changes to settle down.
+  #[arg(long, default_value_t = 500)]
+  debounce: u64,
+
+  /// Reload the DNS server after the regeneration.
+  #[arg(long)]
+  reload_dns: bool,
+
+  /// Recreate the changed services after the regeneration.
+  #[arg(long)]
+  recreate: bool,
+}
+
+#[derive(Args, Debug)]
+struct ConfigArgs {
+  #[command(subcommand)]
+  action: ConfigActions,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigActions {
+  /// Print the effective values merged from all the layers.
+  Show(ConfigShowArgs),
+
+  /// Print the effective value of the dotted key. (e.g. lima.cpus)
+  Get(ConfigKeyArgs),
+
+  /// Set the value of the dotted key in Hills.toml. The value is parsed as TOML or used as a string.
+  Set(ConfigSetArgs),
+
+  /// Remove the dotted key from Hills.toml.
+  Unset(ConfigKeyArgs),
+
+  /// Edit Hills.toml with $VISUAL or $EDITOR. It's saved only if it's valid.
+  Edit,
+}
+
+#[derive(Args, Debug)]
+struct NetworkArgs {
+  #[command(subcommand)]
+  action: NetworkActions,
+}
+
+#[derive(Subcommand, Debug)]
+enum NetworkActions {
+  /// Handle the isolation between the applications by `allow_from`.
+  Policy(NetworkPolicyArgs),
+}
+
+#[derive(Args, Debug)]
+struct NetworkPolicyArgs {
+  #[command(subcommand)]
+  action: NetworkPolicyActions,
+}
+
+#[derive(Subcommand, Debug)]
+enum NetworkPolicyActions {
+  /// Print the reachability between the applications.
+  Show(NetworkPolicyShowArgs),
+
+  /// Install the iptables rules of the policy. It's done by `hills up` too unless all the applications are open.
+  Apply,
+}
+
+#[derive(Args, Debug)]
+struct NetworkPolicyShowArgs {
+  /// Print the iptables rules instead.
+  #[arg(long)]
+  rules: bool,
+}
+
+#[derive(Args, Debug)]
+struct SchemaArgs {
+  #[arg(value_enum, default_value = "config")]
+  kind: SchemaKind,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SchemaKind {
+  /// Hills.toml
+  Config,
+  /// The application TOML.
+  Application,
+  /// The sync sessions of mutagen.
+  Mutagen,
+  /// The template of Lima.
+  Lima,
+}
+
+#[derive(Args, Debug)]
+struct MigrateArgs {
+  /// Print the rewritten files without saving them.
+  #[arg(long)]
+  dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+struct ConfigKeyArgs {
+  key: String,
+}
+
+#[derive(Args, Debug)]
+struct ConfigSetArgs {
+  key: String,
+  value: String,
+}
+
+#[derive(Args, Debug)]
+struct ConfigShowArgs {
+  /// Print the layer where each value comes from.
+  #[arg(long)]
+  origin: bool,
+}
+
+#[derive(Args, Debug)]
+#[group(multiple = false)]
+struct ValidateArgs {
+  /// Default is the application of the current directory.
+  name: Option<String>,
+
+  /// Validate all the applications.
+  #[arg(long)]
+  all: bool,
+}
+
+#[derive(Args, Debug)]
+struct EnvArgs {
+  #[command(subcommand)]
+  action: EnvActions,
+}
+
+#[derive(Subcommand, Debug)]
+enum EnvActions {
+  /// Print the effective value.
+  Get(EnvGetArgs),
+
+  /// Set the value into the layer.
+  Set(EnvSetArgs),
+
+  /// Remove the value from the layer.
+  Unset(EnvUnsetArgs),
+
+  /// List the effective values with the layers where they come from.
+  List(EnvListArgs),
+}
+
+#[derive(Args, Debug)]
+struct EnvGetArgs {
   name: String,
+  key: String,
+}
+
+#[derive(Args, Debug)]
+struct EnvSetArgs {
+  name: String,
+  key: String,
+  value: String,
+
+  /// Layer to write. The secret layer is encrypted with the local key.
+  #[arg(long, value_enum, default_value = "workspace")]
+  layer: Layer,
+}
+
+#[derive(Args, Debug)]
+struct EnvUnsetArgs {
+  name: String,
+  key: String,
+
+  #[arg(long, value_enum, default_value = "workspace")]
+  layer: Layer,
+}
+
+#[derive(Args, Debug)]
+struct EnvListArgs {
+  name: String,
+
+  /// Print the decrypted secrets instead of masking them.
+  #[arg(long)]
+  show_secrets: bool,
 }
 
 #[derive(Args, Debug)]
 struct VmArgs {
-  action: String,
+  #[command(subcommand)]
+  action: VmActions,
+}
+
+#[derive(Subcommand, Debug)]
+enum VmActions {
+  /// Create and start the VM.
+  Up,
+
+  /// Stop the VM.
+  Down,
+
+  /// Delete the VM.
+  Clean,
+
+  /// Show the status of the VM.
+  Status,
+
+  /// Log in to the VM.
+  Ssh,
+
+  /// Run the command in the VM.
+  Exec(VmExecArgs),
+
+  /// Handle the image of the VM.
+  Image(VmImageArgs),
+}
+
+#[derive(Args, Debug)]
+struct VmImageArgs {
+  #[command(subcommand)]
+  action: VmImageActions,
+
+  /// Architecture of the image. Default is the one of the host.
+  #[arg(long, global = true, value_parser = image::normalize_arch)]
+  arch: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum VmImageActions {
+  /// Download the image into the cache.
+  Fetch,
+
+  /// Verify the digest of the cached image.
+  Verify,
+}
+
+#[derive(Args, Debug)]
+struct VmExecArgs {
+  #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+  command: Vec<String>,
 }
 
 fn main() {
   let cli = Cli::parse();
 
-  config::try_to_setup_from(Path::new(&cli.context));
+  config::try_to_setup_from(cli.context.as_deref().map(Path::new));
 
   match cli.action {
     Actions::Init => {
-      config::create(root);
+      config::create(Path::new(cli.context.as_deref().unwrap_or(".")));
     }
     Actions::New(args) => {
       if !config::current().app_root().exists() {
         fs::create_dir_all(*config::current().app_root()).unwrap();
       }
 
-      application::create(&args.name);
+      if args.list_templates {
+        for name in application::template_names() {
+          println!("{}", name);
+        }
+
+        return;
+      }
+
+      let name = args.name.unwrap();
+
+      match args.template {
+        Some(template) => {
+          let vars = args.vars.iter().map(|v| v.split_once('=').map(|(k, v)| (k.to_string(), v.to_string())).expect(&format!("`{}` is not KEY=VALUE", v))).collect();
+
+          application::create_from(&name, &template, vars);
+        }
+        None => application::create(&name),
+      }
     }
-    Actions::Alias(args) => {
-      config::set_alias(&args.original, &args.alias);
+    Actions::Import(args) => match (args.path, args.scan) {
+      (Some(path), _) => {
+        application::import(&path, args.name.as_deref()).unwrap_or_else(|e| panic!("{}", e));
+      }
+      (None, Some(dir)) => {
+        for repo in application::scan(&dir) {
+          if let Err(e) = application::import(&repo, None) {
+            eprintln!("Skipped {:?} : {}", repo, e);
+          }
+        }
+      }
+      (None, None) => unreachable!(),
+    },
+    Actions::Rm(args) => {
+      let app = application::find_by(&config::current().resolve(&args.name));
+
+      if args.purge_repo {
+        app.check_purge().unwrap_or_else(|e| panic!("{}", e));
+      }
+
+      println!("Remove {}:", app.name());
+
+      for step in app.removal_steps(args.keep_volumes, args.purge_repo) {
+        println!("  - {}", step);
+      }
 
-      // if let Some(name) = args.original {
-      //   application::find_by(&c, &name).update(true);
-      // }
+      if !args.yes && !confirm("Are you sure?") {
+        process::exit(1);
+      }
+
+      app.remove(args.keep_volumes, args.purge_repo);
     }
+    Actions::Alias(args) => match args.action {
+      AliasActions::Ls(args) => {
+        let only = args.name.map(|name| config::current().resolve(&name));
+
+        for (alias, name) in config::current().aliases() {
+          if only.as_ref().is_none_or(|o| o.eq(&name)) {
+            println!("{} -> {}", alias, name);
+          }
+        }
+      }
+      AliasActions::Add(args) => {
+        config::add_alias(&args.name, &args.alias);
+      }
+      AliasActions::Rm(args) => {
+        config::remove_alias(&args.alias);
+      }
+      AliasActions::RenameApp(args) => {
+        application::rename(&args.name, &args.new_name);
+      }
+    },
     Actions::List(args) => match &args.name {
       Some(name) => {
         application::find_by(name).print();
@@ -110,29 +600,308 @@ fn main() {
       if vm::should() && !vm::on() {
         panic!("You need to run on vm! Please do vm up");
       }
-      let app = application::find_by(&args.name);
+      let app = application::find_by(&name_or_current(args.name));
+
+      config::current().dns().setup();
+
+      for name in app.depends_on() {
+        application::find_by(&name).update(false);
+      }
 
       app.update(false);
+
+      let policy = policy::current();
+
+      if !policy.is_open() {
+        policy.apply();
+      }
+
+      app.up();
     }
     Actions::Update(args) => {
-      let app = application::find_by(&args.name);
+      let app = application::find_by(&name_or_current(args.name));
 
       app.update(false);
     }
-    Actions::Vm(args) => match args.action.as_str() {
-      "up" => {
-        vm::login();
+    Actions::Hosts(args) => {
+      let only = args.app.map(|name| config::current().resolve(&name));
+      let mut entries: Vec<(IpAddr, String)> = vec![];
+
+      for (name, services) in config::current().dns().leases() {
+        if only.as_ref().is_some_and(|o| !o.eq(&name)) || !application::is_exists(&name) {
+          continue;
+        }
+
+        let domains = application::find_by(&name).domains();
+
+        for (service, lease) in services {
+          for addr in lease.addrs() {
+            for domain in domains.iter() {
+              entries.push((addr, format!("{}.{}", service, domain)));
+            }
+          }
+        }
       }
-      "down" => {
-        vm::shutdown();
+
+      let block = hosts::render(&entries);
+
+      match args.write {
+        Some(file) => hosts::update_block(&file, &args.marker, &block),
+        None => print!("{}", block),
       }
-      "clean" => {
-        vm::destroy();
+    }
+    Actions::SshConfig(args) => {
+      let block = lima_only("ssh-config").ssh_config();
+
+      match args.write {
+        Some(file) => hosts::update_block(&file, &args.marker, &block),
+        None => print!("{}", block),
       }
-      _ => {
-        panic!("Invalid action : {}", args.action);
+    }
+    Actions::Resolver(args) => {
+      let r = config::current().resolver(args.kind);
+
+      match args.action {
+        ResolverActions::Install(write_args) => {
+          for f in r.files() {
+            println!("# {}\n{}", f.path.display(), f.content);
+          }
+
+          if !write_args.dry_run {
+            r.install();
+          }
+        }
+        ResolverActions::Uninstall(write_args) => {
+          for f in r.files() {
+            println!("# {}", f.path.display());
+          }
+
+          if !write_args.dry_run {
+            r.uninstall();
+          }
+        }
+        ResolverActions::Status => {
+          let status = if r.is_installed() { "installed" } else { "not installed" };
+
+          println!("{:?}: {}", r.kind(), status);
+
+          for f in r.files() {
+            println!("  {}", f.path.display());
+          }
+        }
+      }
+    }
+    Actions::Env(args) => match args.action {
+      EnvActions::Get(args) => {
+        let app = application::find_by(&args.name);
+
+        match app.effective_env().get(&args.key) {
+          Some((_, value)) => println!("{}", value),
+          None => {
+            eprintln!("Not found {} in {}", args.key, app.name());
+            process::exit(1);
+          }
+        }
+      }
+      EnvActions::Set(args) => {
+        let app = application::find_by(&args.name);
+
+        app.set_env(args.layer, &args.key, &args.value);
+        refresh_env(&app);
+      }
+      EnvActions::Unset(args) => {
+        let app = application::find_by(&args.name);
+
+        if !app.unset_env(args.layer, &args.key) {
+          eprintln!("Not found {} in the {:?} layer of {}", args.key, args.layer, app.name());
+          process::exit(1);
+        }
+
+        refresh_env(&app);
+      }
+      EnvActions::List(args) => {
+        for (key, (layer, value)) in application::find_by(&args.name).effective_env() {
+          let value = if layer == Layer::Secret && !args.show_secrets { "********".to_string() } else { value };
+
+          println!("{}={}\t# {:?}", key, value, layer);
+        }
       }
     },
+    Actions::Config(args) => match args.action {
+      ConfigActions::Show(args) => {
+        for (key, value, origin) in config::current().entries() {
+          if args.origin {
+            println!("{} = {}\t# {}", key, value, origin);
+          } else {
+            println!("{} = {}", key, value);
+          }
+        }
+      }
+      ConfigActions::Get(args) => match config::get(&args.key) {
+        Some(toml::Value::String(s)) => println!("{}", s),
+        Some(toml::Value::Table(t)) => print!("{}", toml::to_string(t).unwrap()),
+        Some(v) => println!("{}", v),
+        None => {
+          eprintln!("Not found {}", args.key);
+          process::exit(1);
+        }
+      },
+      ConfigActions::Set(args) => {
+        config::set(&args.key, &args.value);
+      }
+      ConfigActions::Unset(args) => {
+        config::unset(&args.key);
+      }
+      ConfigActions::Edit => {
+        config::edit();
+      }
+    },
+    Actions::Validate(args) => {
+      let names = match args.all {
+        true => config::current().application_names(),
+        false => vec![config::current().resolve(&name_or_current(args.name))],
+      };
+      let mut failed = false;
+
+      for name in names {
+        let errors = match application::load(&name) {
+          Ok(app) => app.check(),
+          Err(e) => vec![e],
+        };
+
+        if errors.is_empty() {
+          println!("{} : OK", name);
+        } else {
+          errors.iter().for_each(|e| eprintln!("{} : {}", name, e));
+          failed = true;
+        }
+      }
+
+      if failed {
+        process::exit(1);
+      }
+    }
+    Actions::Migrate(args) => {
+      migration::migrate_file(migration::Schema::Config, config::current().path(), config::VERSION, args.dry_run);
+
+      for name in config::current().application_names() {
+        migration::migrate_file(migration::Schema::Application, application::file_path(&name), application::VERSION, args.dry_run);
+      }
+    }
+    Actions::Schema(args) => {
+      let schema = match args.kind {
+        SchemaKind::Config => config::schema(),
+        SchemaKind::Application => application::schema(),
+        SchemaKind::Mutagen => mutagen::schema(),
+        SchemaKind::Lima => lima::schema(),
+      };
+
+      println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+    }
+    Actions::Network(args) => match args.action {
+      NetworkActions::Policy(args) => match args.action {
+        NetworkPolicyActions::Show(args) => match args.rules {
+          true => print!("{}", policy::current().scripts()),
+          false => print!("{}", policy::current().matrix()),
+        },
+        NetworkPolicyActions::Apply => {
+          policy::current().apply();
+        }
+      },
+    },
+    Actions::Doctor => {
+      if !doctor::run() {
+        process::exit(1);
+      }
+    }
+    Actions::Watch(args) => {
+      watcher::new(Duration::from_millis(args.interval), Duration::from_millis(args.debounce), args.reload_dns, args.recreate).run();
+    }
+    Actions::Vm(args) => {
+      let backend = vm::current();
+
+      match args.action {
+        VmActions::Up => {
+          vm::up(backend.as_ref());
+        }
+        VmActions::Down => {
+          vm::down(backend.as_ref());
+        }
+        VmActions::Clean => {
+          vm::destroy(backend.as_ref());
+        }
+        VmActions::Status => {
+          println!("{}: {:?}", backend.name(), backend.status());
+        }
+        VmActions::Ssh => {
+          backend.ssh();
+        }
+        VmActions::Exec(exec_args) => {
+          if !backend.exec(&exec_args.command) {
+            process::exit(1);
+          }
+        }
+        VmActions::Image(image_args) => {
+          let lima = lima_only("vm image");
+          let arch = image_args.arch.unwrap_or_else(image::arch);
+
+          match image_args.action {
+            VmImageActions::Fetch => {
+              lima.images().fetch(&arch);
+            }
+            VmImageActions::Verify => {
+              if !lima.images().verify(&arch) {
+                eprintln!("Digest mismatch for the image of {}", arch);
+                process::exit(1);
+              }
+
+              println!("OK");
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+/// Name of the argument, or the application that contains the current directory.
+fn name_or_current(name: Option<String>) -> String {
+  if let Some(name) = name {
+    return name;
+  }
+
+  let cwd = env::current_dir().expect("Failed to get the current directory");
+
+  return application::infer_from(&cwd).unwrap_or_else(|| panic!("Specify the application. {:?} isn't in any application", cwd));
+}
+
+/// Lima of the workspace. The other backends don't have the images and the ssh config of lima.
+fn lima_only(action: &str) -> lima::Lima {
+  let kind = config::current().vm_kind();
+
+  if kind != vm::Kind::Lima {
+    eprintln!("`hills {}` is only for the lima backend. The backend is {:?}", action, kind);
+    process::exit(1);
+  }
+
+  return config::current().lima();
+}
+
+/// Ask yes or no on the terminal. It's no by default.
+fn confirm(message: &str) -> bool {
+  let mut answer = String::new();
+
+  print!("{} [y/N] ", message);
+  io::stdout().flush().unwrap();
+  io::stdin().read_line(&mut answer).expect("Failed to read the answer");
+
+  return matches!(answer.trim().to_lowercase().as_str(), "y" | "yes");
+}
+
+/// Regenerate the env files if the application is already updated.
+fn refresh_env(app: &application::Application) {
+  if app.dist_root().exists() && app.compose_file_path().exists() {
+    app.update(true);
   }
 }
 
@@ -0,0 +1,124 @@
+use crate::config;
+use std::fs;
+use std::path::PathBuf;
+use toml_edit::{value, Document};
+
+/// Schema of the file that's migrated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schema {
+  /// Hills.toml
+  Config,
+  /// `<app_root>/<app>.toml`
+  Application,
+}
+
+/// Step that upgrades the file from the version to the next one.
+pub struct Migration {
+  pub schema: Schema,
+  pub from: u32,
+  pub description: &'static str,
+  apply: fn(&mut Document),
+}
+
+/// The files without `version` are version 0 that's before the versioning.
+const MIGRATIONS: [Migration; 2] = [
+  Migration {
+    schema: Schema::Config,
+    from: 0,
+    description: "Add the version",
+    apply: |_| {},
+  },
+  Migration {
+    schema: Schema::Application,
+    from: 0,
+    description: "Remove the empty repository written by the old template",
+    apply: |doc| {
+      if doc.get("repository").and_then(|r| r.as_str()).is_some_and(|r| r.is_empty()) {
+        doc.remove("repository");
+      }
+    },
+  },
+];
+
+pub fn version_of(doc: &Document) -> Result<u32, String> {
+  return match doc.get("version") {
+    None => Ok(0),
+    Some(v) => v.as_integer().and_then(|v| u32::try_from(v).ok()).ok_or(format!("`{}` is not a version", v.to_string().trim())),
+  };
+}
+
+/// Upgrade the document to the latest version step by step. It refuses the newer version not to downgrade it silently.
+pub fn migrate(schema: Schema, doc: &mut Document, latest: u32) -> Result<Vec<&'static str>, String> {
+  let version = version_of(doc)?;
+  let mut applied: Vec<&'static str> = vec![];
+
+  if version > latest {
+    return Err(format!("Version {} is newer than {} that this hills supports. Upgrade hills", version, latest));
+  }
+
+  for from in version..latest {
+    let m = MIGRATIONS
+      .iter()
+      .find(|m| m.schema == schema && m.from == from)
+      .ok_or(format!("Not found the migration of {:?} from version {}", schema, from))?;
+
+    (m.apply)(doc);
+    doc["version"] = value(i64::from(from + 1));
+    applied.push(m.description);
+  }
+
+  // The version is the first key of the file.
+  doc.as_table_mut().sort_values_by(|k1, _, k2, _| (k1.get() != "version").cmp(&(k2.get() != "version")));
+
+  return Ok(applied);
+}
+
+/// Migrate the content of the file. It's returned as it is if it's the latest.
+pub fn migrate_str(schema: Schema, s: &str, latest: u32) -> Result<String, String> {
+  let mut doc = s.parse::<Document>().map_err(|e| e.to_string())?;
+
+  if migrate(schema, &mut doc, latest)?.is_empty() {
+    return Ok(s.to_string());
+  }
+
+  return Ok(doc.to_string());
+}
+
+/// Migrate the file and print the steps with the rewritten content. The file isn't saved in the dry run.
+pub fn migrate_file(schema: Schema, f: Box<PathBuf>, latest: u32, dry_run: bool) {
+  let s = fs::read_to_string(f.as_ref()).expect(&format!("Failed to read {:?}", f));
+  let mut doc = s.parse::<Document>().expect(&format!("Failed to parse {:?}", f));
+  let applied = migrate(schema, &mut doc, latest).unwrap_or_else(|e| panic!("Failed to migrate {:?} : {}", f, e));
+
+  if applied.is_empty() {
+    println!("{:?} is up to date", f);
+    return;
+  }
+
+  println!("# {}", f.display());
+
+  for description in applied {
+    println!("# - {}", description);
+  }
+
+  print!("{}", doc);
+
+  if !dry_run {
+    config::create_file(f, doc.to_string());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn old_files_are_upgraded_and_newer_ones_are_refused() {
+    let migrated = migrate_str(Schema::Application, "# app\npath = \"repos/web\"\nrepository = \"\"\n", 1);
+
+    assert_eq!(migrated, Ok("version = 1\n# app\npath = \"repos/web\"\n".to_string()));
+    assert_eq!(migrate_str(Schema::Application, "version = 1\npath = \"repos/web\"\n", 1), Ok("version = 1\npath = \"repos/web\"\n".to_string()));
+    assert!(migrate_str(Schema::Config, "version = 2\n", 1).unwrap_err().contains("Upgrade hills"));
+    assert!(migrate_str(Schema::Config, "version = \"one\"\n", 1).is_err());
+  }
+}
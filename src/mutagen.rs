@@ -2,22 +2,29 @@ mod template;
 
 use crate::application;
 use crate::config;
+use schemars::schema::RootSchema;
 use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
+use std::process;
 
 pub struct Mutagen {
   path: String,
   name: String,
 }
 
+/// JSON Schema of the sync sessions.
+pub fn schema() -> RootSchema {
+  return template::schema();
+}
+
 pub fn new(path: String) -> Mutagen {
   return Mutagen { path: path, name: "hills".to_string() };
 }
 
 impl Mutagen {
   pub fn update(&self) {
-    template::ensure_default(&self);
+    template::ensure_default(self);
 
     let mut sync: BTreeMap<String, template::Sync> = BTreeMap::new();
 
@@ -25,7 +32,9 @@ impl Mutagen {
       let entry = entry.unwrap();
       let path = entry.path();
 
-      if path.is_file() && (path.ends_with(".yml") || path.ends_with(".yaml")) {
+      let is_yaml = path.extension().is_some_and(|e| e == "yml" || e == "yaml");
+
+      if path.is_file() && is_yaml {
         load(Box::new(path)).sync.into_iter().for_each(|(k, v)| {
           sync.insert(k, v);
         });
@@ -35,6 +44,11 @@ impl Mutagen {
     config::current().application_names().into_iter().for_each(|name| {
       let app = application::find_by(&name);
 
+      if !app.is_synced() {
+        sync.remove(&name);
+        return;
+      }
+
       if !sync.contains_key(&name) {
         sync.insert(
           name.clone(),
@@ -52,16 +66,43 @@ impl Mutagen {
       let s = sync.get_mut(&name).unwrap();
 
       s.alpha = Some(app.root().to_str().unwrap().to_string());
-      s.beta = Some(format!("hills:{:?}", PathBuf::from("/usr/src/app").join(app.root().as_ref())));
+      s.beta = Some(format!("{}:{:?}", self.name, PathBuf::from("/usr/src/app").join(app.root().as_ref())));
+
+      if let Some(mode) = app.sync_mode() {
+        s.mode = Some(mode);
+      }
+
+      let ignore = app.sync_ignore();
+
+      if !ignore.is_empty() {
+        let i = s.ignore.get_or_insert(template::Ignore { vcs: None, paths: None });
+
+        i.paths.get_or_insert(vec![]).extend(ignore);
+      }
     });
+
+    config::create_file(self.file_path(), serde_yaml::to_string(&template::Yaml { sync: sync }).unwrap());
+  }
+
+  /// Terminate the sync session of the application. It's skipped if mutagen isn't installed.
+  pub fn terminate(&self, name: &str) {
+    let mut cmd = process::Command::new("mutagen");
+
+    cmd.args(["sync", "terminate", name]);
+
+    match cmd.status() {
+      Ok(status) if !status.success() => eprintln!("Failed to terminate the sync session {}", name),
+      Err(_) => eprintln!("Skipped terminating the sync session {} : mutagen is not found", name),
+      _ => {}
+    }
   }
 
   fn file_path(&self) -> Box<PathBuf> {
     return Box::new(config::current().dist_root().join("mutagen.yml"));
   }
 
-  fn root(&self) -> Box<PathBuf> {
-    let dir = config::current().root().join(self.path);
+  pub fn root(&self) -> Box<PathBuf> {
+    let dir = config::current().root().join(&self.path);
 
     if !dir.exists() {
       fs::create_dir_all(dir.clone()).expect(&format!("Failed to create {:?}", dir));
@@ -1,5 +1,7 @@
 use std::collections::BTreeMap;
 
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
@@ -8,13 +10,13 @@ use crate::config;
 use super::Mutagen;
 
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct Yaml {
   pub sync: BTreeMap<String, Sync>,
 }
 
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct Sync {
   pub alpha: Option<String>,
   pub beta: Option<String>,
@@ -25,26 +27,34 @@ pub struct Sync {
 }
 
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug)]
-struct Ignore {
-  vcs: Option<bool>,
-  paths: Option<Vec<String>>,
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct Ignore {
+  pub vcs: Option<bool>,
+  pub paths: Option<Vec<String>>,
 }
 
 #[allow(non_snake_case)]
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug)]
-struct Permissions {
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct Permissions {
   defaultFileMode: Option<String>,
   defaultDirectoryMode: Option<String>,
 }
 
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug)]
-struct SymLink {
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct SymLink {
   mode: Option<String>,
 }
 
+pub fn schema() -> RootSchema {
+  let mut schema = schema_for!(Yaml);
+
+  schema.schema.metadata().title = Some("Mutagen".to_string());
+
+  return schema;
+}
+
 pub fn ensure_default(mutagen: &Mutagen) {
   let file = mutagen.root().join("default.yml");
 
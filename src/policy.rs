@@ -0,0 +1,186 @@
+use crate::application;
+use crate::config;
+use crate::docker_compose::engine;
+use crate::vm;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::process;
+
+/// Chain jumped from DOCKER-USER. It's flushed and rebuilt every time the policy is applied.
+const CHAIN: &str = "HILLS-POLICY";
+
+/// Reachability between the applications. They're isolated by their subnets in the shared network.
+pub struct Policy {
+  /// Applications allowed to connect by the application name. None means all of them.
+  allowed: BTreeMap<String, Option<Vec<String>>>,
+}
+
+pub fn new(allowed: BTreeMap<String, Option<Vec<String>>>) -> Policy {
+  return Policy { allowed: allowed };
+}
+
+/// Policy of the applications in the workspace. The aliases in `allow_from` are resolved. The invalid applications are skipped.
+pub fn current() -> Policy {
+  let c = config::current();
+  let allowed = c
+    .application_names()
+    .into_iter()
+    .filter_map(|name| match application::load(&name) {
+      Ok(app) => {
+        let from = app.allow_from().map(|list| list.iter().map(|n| c.try_resolve(n).unwrap_or(n.clone())).collect());
+
+        Some((name, from))
+      }
+      Err(e) => {
+        eprintln!("Skipped {} : {}", name, e);
+        None
+      }
+    })
+    .collect();
+
+  return new(allowed);
+}
+
+impl Policy {
+  /// Whether all the applications can reach each other. The rules aren't needed then.
+  pub fn is_open(&self) -> bool {
+    return self.allowed.values().all(|a| a.is_none());
+  }
+
+  pub fn allows(&self, from: &str, to: &str) -> bool {
+    return from == to || self.allowed.get(to).is_none_or(|a| a.as_ref().is_none_or(|list| list.iter().any(|n| n == from)));
+  }
+
+  /// Pairs of the source and the destination that are blocked.
+  pub fn denied(&self) -> Vec<(String, String)> {
+    let names = self.allowed.keys();
+
+    return names.clone().flat_map(|from| names.clone().filter(move |to| !self.allows(from, to)).map(move |to| (from.clone(), to.clone()))).collect();
+  }
+
+  /// Table of the reachability. The rows are the sources and the columns are the destinations.
+  pub fn matrix(&self) -> String {
+    let names = self.allowed.keys().collect::<Vec<&String>>();
+    let header = "FROM \\ TO";
+    let width = names.iter().map(|n| n.len()).chain([header.len()]).max().unwrap();
+    let widths = names.iter().map(|n| n.len().max("allow".len())).collect::<Vec<usize>>();
+    let mut lines: Vec<String> = vec![];
+
+    let row = |first: &str, cells: Vec<&str>| -> String {
+      let cells = cells.iter().zip(widths.iter()).map(|(c, w)| format!("{:<w$}", c, w = w)).collect::<Vec<String>>();
+
+      return format!("{:<width$}  {}", first, cells.join("  "), width = width).trim_end().to_string();
+    };
+
+    lines.push(row(header, names.iter().map(|n| n.as_str()).collect()));
+
+    for from in names.iter() {
+      lines.push(row(from, names.iter().map(|to| if self.allows(from, to) { "allow" } else { "deny" }).collect()));
+    }
+
+    return lines.join("\n") + "\n";
+  }
+
+  /// Shell script that installs the iptables rules jumped from the chain of the engine. The replies of the allowed connections pass through.
+  /// The applications share the bridge network, so it fails unless the bridged packets are passed to iptables.
+  pub fn script<N: Display>(&self, command: &str, hook: &str, subnets: &BTreeMap<String, N>) -> String {
+    let sysctl = format!("net.bridge.bridge-nf-call-{}", command);
+    let mut lines = vec![
+      format!(
+        "[ \"$(cat /proc/sys/{} 2>/dev/null)\" = 1 ] || {{ echo '{} is off. Enable it by modprobe br_netfilter and sysctl -w {}=1' >&2; exit 1; }}",
+        sysctl.replace('.', "/"),
+        sysctl,
+        sysctl
+      ),
+      format!("{} -N {} 2>/dev/null || true", command, CHAIN),
+      format!("{} -F {}", command, CHAIN),
+      format!("{} -C {} -j {} 2>/dev/null || {} -I {} -j {}", command, hook, CHAIN, command, hook, CHAIN),
+      format!("{} -A {} -m conntrack --ctstate ESTABLISHED,RELATED -j RETURN", command, CHAIN),
+    ];
+
+    for (from, to) in self.denied() {
+      if let (Some(src), Some(dst)) = (subnets.get(&from), subnets.get(&to)) {
+        lines.push(format!("{} -A {} -s {} -d {} -j DROP", command, CHAIN, src, dst));
+      }
+    }
+
+    return lines.join("\n") + "\n";
+  }
+
+  /// Scripts for IPv4 and IPv6 of the subnets assigned to the applications.
+  pub fn scripts(&self) -> String {
+    let dns = config::current().dns();
+    let engine = engine::current();
+    let mut s = self.script("iptables", engine.filter_chain(), &dns.subnets());
+    let subnets6 = dns.subnets6();
+
+    if !subnets6.is_empty() {
+      s.push_str(&self.script("ip6tables", engine.filter_chain(), &subnets6));
+    }
+
+    return s;
+  }
+
+  /// Install the rules where the containers run. It needs sudo. They're run through the VM unless this is the guest or the host of the backend `none`.
+  pub fn apply(&self) {
+    if config::current().dns().subnets().is_empty() {
+      eprintln!("Warning: No subnets are assigned yet. The network policy isn't applied");
+      return;
+    }
+
+    let script = format!("set -e\n{}", self.scripts());
+    let succeeded = match vm::on() {
+      true => {
+        let mut cmd = process::Command::new("sudo");
+
+        cmd.arg("sh").arg("-c").arg(&script);
+        cmd.status().expect(&format!("Failed to execute {:?}", cmd)).success()
+      }
+      false => vm::current().exec(&["sudo".to_string(), "sh".to_string(), "-c".to_string(), script]),
+    };
+
+    if !succeeded {
+      panic!("Failed to apply the network policy");
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ipnet::Ipv4Net;
+
+  fn policy() -> Policy {
+    return new(BTreeMap::from([
+      ("db".to_string(), Some(vec!["web".to_string()])),
+      ("gateway".to_string(), None),
+      ("web".to_string(), Some(vec!["gateway".to_string()])),
+    ]));
+  }
+
+  #[test]
+  fn only_the_allowed_applications_reach() {
+    let p = policy();
+
+    assert!(!p.is_open());
+    assert!(p.allows("web", "db"));
+    assert!(!p.allows("gateway", "db"));
+    assert!(p.allows("db", "gateway"));
+    assert_eq!(
+      p.matrix(),
+      "FROM \\ TO  db     gateway  web\ndb         allow  allow    deny\ngateway    deny   allow    allow\nweb        allow  allow    allow\n"
+    );
+  }
+
+  #[test]
+  fn denied_pairs_are_dropped_by_the_subnets() {
+    let subnets: BTreeMap<String, Ipv4Net> = BTreeMap::from([("db".to_string(), "172.31.2.0/24".parse().unwrap()), ("gateway".to_string(), "172.31.3.0/24".parse().unwrap())]);
+    let script = policy().script("iptables", "DOCKER-USER", &subnets);
+
+    assert!(script.starts_with("[ \"$(cat /proc/sys/net/bridge/bridge-nf-call-iptables 2>/dev/null)\" = 1 ] || "));
+    assert!(script.contains("iptables -C DOCKER-USER -j HILLS-POLICY 2>/dev/null || iptables -I DOCKER-USER -j HILLS-POLICY\n"));
+    assert!(script.contains("iptables -A HILLS-POLICY -s 172.31.3.0/24 -d 172.31.2.0/24 -j DROP\n"));
+    assert!(!script.contains("-d 172.31.3.0/24"));
+    assert!(policy().script("ip6tables", "FORWARD", &subnets).contains("ip6tables -I FORWARD -j HILLS-POLICY\n"));
+  }
+}
@@ -0,0 +1,157 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+
+/// Resolver of the host that forwards the queries for the domain to the DNS server of hills.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+  /// A drop-in of systemd-resolved.
+  SystemdResolved,
+  /// `/etc/resolver/<domain>` of macOS.
+  Macos,
+  /// A snippet of dnsmasq.
+  Dnsmasq,
+}
+
+pub struct File {
+  pub path: Box<PathBuf>,
+  pub content: String,
+}
+
+pub struct Resolver {
+  kind: Kind,
+  domain: String,
+  address: Ipv4Addr,
+  port: u16,
+}
+
+pub fn new(kind: Kind, domain: String, address: Ipv4Addr, port: u16) -> Resolver {
+  return Resolver {
+    kind: kind,
+    domain: domain,
+    address: address,
+    port: port,
+  };
+}
+
+/// Guess the resolver used by the host.
+pub fn detect() -> Kind {
+  let os = process::Command::new("uname").arg("-s").output().expect("Failed to execute uname").stdout.iter().map(|&x| x as char).collect::<String>();
+
+  if os.trim() == "Darwin" {
+    return Kind::Macos;
+  }
+
+  if Path::new("/run/systemd/resolve").exists() {
+    return Kind::SystemdResolved;
+  }
+
+  if Path::new("/etc/dnsmasq.d").exists() {
+    return Kind::Dnsmasq;
+  }
+
+  panic!("Not found the resolver of the host. Please set resolver.kind in Hills.toml");
+}
+
+impl Resolver {
+  pub fn kind(&self) -> Kind {
+    return self.kind;
+  }
+
+  pub fn files(&self) -> Vec<File> {
+    return match self.kind {
+      Kind::SystemdResolved => vec![File {
+        path: Box::new(PathBuf::from("/etc/systemd/resolved.conf.d/hills.conf")),
+        content: format!("[Resolve]\nDNS={}:{}\nDomains=~{}\n", self.address, self.port, self.domain),
+      }],
+      Kind::Macos => vec![File {
+        path: Box::new(PathBuf::from("/etc/resolver").join(&self.domain)),
+        content: format!("nameserver {}\nport {}\n", self.address, self.port),
+      }],
+      Kind::Dnsmasq => vec![File {
+        path: Box::new(PathBuf::from("/etc/dnsmasq.d/hills.conf")),
+        content: format!("server=/{}/{}#{}\n", self.domain, self.address, self.port),
+      }],
+    };
+  }
+
+  pub fn install(&self) {
+    for f in self.files() {
+      sudo(&["mkdir", "-p", f.path.parent().unwrap().to_str().unwrap()], None);
+      sudo(&["tee", f.path.to_str().unwrap()], Some(&f.content));
+      println!("Saved {:?}", f.path);
+    }
+
+    self.reload();
+  }
+
+  pub fn uninstall(&self) {
+    for f in self.files() {
+      if f.path.exists() {
+        sudo(&["rm", "-f", f.path.to_str().unwrap()], None);
+        println!("Removed {:?}", f.path);
+      }
+    }
+
+    self.reload();
+  }
+
+  /// Whether all the files are installed with the expected content.
+  pub fn is_installed(&self) -> bool {
+    return self.files().iter().all(|f| fs::read_to_string(f.path.as_ref()).is_ok_and(|s| s == f.content));
+  }
+
+  fn reload(&self) {
+    match self.kind {
+      Kind::SystemdResolved => sudo(&["systemctl", "restart", "systemd-resolved"], None),
+      Kind::Macos => sudo(&["killall", "-HUP", "mDNSResponder"], None),
+      Kind::Dnsmasq => sudo(&["systemctl", "restart", "dnsmasq"], None),
+    }
+  }
+}
+
+/// Run the command as root. The stdin is given to the command if it's some.
+pub fn sudo(args: &[&str], stdin: Option<&str>) {
+  let mut cmd = process::Command::new("sudo");
+
+  cmd.args(args).stdout(Stdio::null());
+
+  if stdin.is_some() {
+    cmd.stdin(Stdio::piped());
+  }
+
+  let mut child = cmd.spawn().expect(&format!("Failed to execute sudo {:?}", args));
+
+  if let Some(s) = stdin {
+    child.stdin.take().unwrap().write_all(s.as_bytes()).expect(&format!("Failed to write to sudo {:?}", args));
+  }
+
+  if !child.wait().expect(&format!("Failed to execute sudo {:?}", args)).success() {
+    panic!("Failed to execute sudo {:?}", args);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn files_point_the_domain_at_the_forwarded_port() {
+    let addr = Ipv4Addr::new(127, 0, 0, 1);
+
+    let f = &new(Kind::SystemdResolved, "local".to_string(), addr, 53).files()[0];
+    assert_eq!(f.content, "[Resolve]\nDNS=127.0.0.1:53\nDomains=~local\n");
+
+    let f = &new(Kind::Macos, "hills.test".to_string(), addr, 5353).files()[0];
+    assert_eq!(*f.path, PathBuf::from("/etc/resolver/hills.test"));
+    assert_eq!(f.content, "nameserver 127.0.0.1\nport 5353\n");
+
+    let f = &new(Kind::Dnsmasq, "local".to_string(), addr, 53).files()[0];
+    assert_eq!(f.content, "server=/local/127.0.0.1#53\n");
+  }
+}
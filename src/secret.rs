@@ -0,0 +1,94 @@
+use crate::config;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+/// Length of the nonce of AES-GCM.
+const NONCE_SIZE: usize = 12;
+
+pub struct Cipher {
+  cipher: Aes256Gcm,
+}
+
+pub fn new(key: &[u8]) -> Cipher {
+  return Cipher {
+    cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+  };
+}
+
+/// Key file that's local to the developer. It's generated at the first time.
+pub fn key_path() -> Box<PathBuf> {
+  return Box::new(config::user_dir().join("secret.key"));
+}
+
+/// Cipher with the local key.
+pub fn load() -> Cipher {
+  let path = key_path();
+
+  if !path.exists() {
+    let key = Aes256Gcm::generate_key(OsRng);
+    let dir = path.parent().unwrap();
+
+    fs::create_dir_all(dir).expect(&format!("Failed to create {:?}", dir));
+
+    let mut f = OpenOptions::new().write(true).create_new(true).mode(0o600).open(path.as_ref()).expect(&format!("Failed to write {:?}", path));
+
+    write!(f, "{}", STANDARD.encode(key)).expect(&format!("Failed to write {:?}", path));
+    println!("Saved {:?}", path);
+  }
+
+  let s = fs::read_to_string(path.as_ref()).expect(&format!("Failed to read {:?}", path));
+  let key = STANDARD.decode(s.trim()).expect(&format!("Invalid key {:?}", path));
+
+  if key.len() != 32 {
+    panic!("Invalid key {:?} : It has to be 32 bytes", path);
+  }
+
+  return new(&key);
+}
+
+impl Cipher {
+  /// Encrypt the value into base64 of the nonce and the ciphertext.
+  pub fn encrypt(&self, plain: &str) -> String {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let mut bytes = nonce.to_vec();
+
+    bytes.extend(self.cipher.encrypt(&nonce, plain.as_bytes()).expect("Failed to encrypt"));
+
+    return STANDARD.encode(bytes);
+  }
+
+  pub fn decrypt(&self, encrypted: &str) -> Result<String, String> {
+    let bytes = STANDARD.decode(encrypted).map_err(|e| e.to_string())?;
+
+    if bytes.len() < NONCE_SIZE {
+      return Err("Too short".to_string());
+    }
+
+    let (nonce, ciphertext) = bytes.split_at(NONCE_SIZE);
+    let plain = self.cipher.decrypt(Nonce::from_slice(nonce), ciphertext).map_err(|_| "Failed to decrypt. The key may be different".to_string())?;
+
+    return String::from_utf8(plain).map_err(|e| e.to_string());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn encrypted_value_is_decrypted_only_with_the_same_key() {
+    let cipher = new(&[1; 32]);
+    let encrypted = cipher.encrypt("s3cr3t");
+
+    assert_ne!(encrypted, "s3cr3t");
+    assert_ne!(encrypted, cipher.encrypt("s3cr3t"));
+    assert_eq!(cipher.decrypt(&encrypted), Ok("s3cr3t".to_string()));
+    assert!(new(&[2; 32]).decrypt(&encrypted).is_err());
+  }
+}
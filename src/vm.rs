@@ -1,23 +1,201 @@
+mod native;
+
+use crate::config;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::process;
 
+/// VM implementation selected by `vm.backend` in Hills.toml.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+  Lima,
+  Colima,
+  /// Use the docker running on the host directly. (e.g. native Linux)
+  None,
+}
+
+/// Resources given to the guest.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capacity {
+  pub cpus: u32,
+  /// Memory in bytes.
+  pub memory: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+  NotCreated,
+  PowerOff,
+  Aborted,
+  Running,
+  /// Transitional or unrecognized states. (e.g. Starting)
+  Unknown,
+}
+
+pub trait Backend {
+  /// Name of the instance managed by the backend.
+  fn name(&self) -> String;
+
+  /// Hostname seen inside the guest.
+  fn hostname(&self) -> String;
+
+  /// Whether the applications have to be run inside the guest.
+  fn requires_guest(&self) -> bool;
+
+  /// Render the configuration file for the backend into `.dist`.
+  fn render_config(&self);
+
+  fn start(&self);
+
+  fn stop(&self);
+
+  fn delete(&self);
+
+  fn status(&self) -> Status;
+
+  /// Run the command in the guest and return whether it succeeded.
+  fn exec(&self, args: &[String]) -> bool;
+
+  /// Log in to the guest interactively.
+  fn ssh(&self);
+
+  /// Resources of the guest. None if the containers run on the host.
+  fn capacity(&self) -> Option<Capacity> {
+    return None;
+  }
+}
+
+pub fn current() -> Box<dyn Backend> {
+  let c = config::current();
+
+  return match c.vm_kind() {
+    Kind::Lima => Box::new(c.lima()),
+    Kind::Colima => Box::new(c.colima()),
+    Kind::None => Box::new(native::new()),
+  };
+}
+
 pub fn on() -> bool {
-  let uname = process::Command::new("uname").arg("-a").output().expect("failed to execute uname").stdout.iter().map(|&x| x as char).collect::<String>();
-  let parts = uname.split(" ").collect::<Vec<&str>>().into_iter().take(2);
+  let hostname = process::Command::new("uname").arg("-n").output().expect("failed to execute uname").stdout.iter().map(|&x| x as char).collect::<String>();
 
-  return parts.eq(vec!["Linux", "lima-hills"]);
+  return hostname.trim() == current().hostname();
 }
 
 pub fn should() -> bool {
-  let uname = process::Command::new("uname").arg("-a").output().expect("failed to execute uname").stdout.iter().map(|&x| x as char).collect::<String>();
-  let parts = uname.split(" ").collect::<Vec<&str>>().into_iter().take(1);
+  return current().requires_guest();
+}
 
-  return !parts.eq(vec!["Linux"]);
+pub fn up(backend: &dyn Backend) {
+  backend.render_config();
+
+  if backend.status() != Status::Running {
+    backend.start();
+  }
+}
+
+pub fn down(backend: &dyn Backend) {
+  if backend.status() == Status::Running {
+    backend.stop();
+  }
 }
 
-pub fn login() {
-  // Lima::new().start()
+pub fn destroy(backend: &dyn Backend) {
+  if backend.status() == Status::Running {
+    backend.stop();
+  }
+
+  if backend.status() != Status::NotCreated {
+    backend.delete();
+  }
 }
 
-pub fn shutdown() {}
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::cell::RefCell;
+
+  struct Fake {
+    status: RefCell<Status>,
+    calls: RefCell<Vec<&'static str>>,
+  }
+
+  fn fake(status: Status) -> Fake {
+    return Fake {
+      status: RefCell::new(status),
+      calls: RefCell::new(vec![]),
+    };
+  }
+
+  impl Backend for Fake {
+    fn name(&self) -> String {
+      return "fake".to_string();
+    }
+
+    fn hostname(&self) -> String {
+      return "fake-hills".to_string();
+    }
+
+    fn requires_guest(&self) -> bool {
+      return true;
+    }
+
+    fn render_config(&self) {
+      self.calls.borrow_mut().push("render_config");
+    }
+
+    fn start(&self) {
+      self.calls.borrow_mut().push("start");
+      *self.status.borrow_mut() = Status::Running;
+    }
 
-pub fn destroy() {}
+    fn stop(&self) {
+      self.calls.borrow_mut().push("stop");
+      *self.status.borrow_mut() = Status::PowerOff;
+    }
+
+    fn delete(&self) {
+      self.calls.borrow_mut().push("delete");
+      *self.status.borrow_mut() = Status::NotCreated;
+    }
+
+    fn status(&self) -> Status {
+      return *self.status.borrow();
+    }
+
+    fn exec(&self, _args: &[String]) -> bool {
+      self.calls.borrow_mut().push("exec");
+      return true;
+    }
+
+    fn ssh(&self) {
+      self.calls.borrow_mut().push("ssh");
+    }
+  }
+
+  #[test]
+  fn up_starts_only_when_not_running() {
+    let vm = fake(Status::NotCreated);
+    up(&vm);
+    up(&vm);
+
+    assert_eq!(*vm.calls.borrow(), vec!["render_config", "start", "render_config"]);
+  }
+
+  #[test]
+  fn destroy_stops_before_delete() {
+    let vm = fake(Status::Running);
+    destroy(&vm);
+
+    assert_eq!(*vm.calls.borrow(), vec!["stop", "delete"]);
+    assert_eq!(vm.status(), Status::NotCreated);
+  }
+
+  #[test]
+  fn down_does_nothing_unless_running() {
+    let vm = fake(Status::Aborted);
+    down(&vm);
+
+    assert!(vm.calls.borrow().is_empty());
+  }
+}
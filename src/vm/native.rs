@@ -0,0 +1,54 @@
+use super::{Backend, Status};
+use std::process;
+
+/// Backend for the host that is able to run docker by itself.
+pub struct Native {}
+
+pub fn new() -> Native {
+  return Native {};
+}
+
+impl Backend for Native {
+  fn name(&self) -> String {
+    return "none".to_string();
+  }
+
+  fn hostname(&self) -> String {
+    return process::Command::new("uname")
+      .arg("-n")
+      .output()
+      .expect("Failed to execute uname")
+      .stdout
+      .iter()
+      .map(|&x| x as char)
+      .collect::<String>()
+      .trim()
+      .to_string();
+  }
+
+  fn requires_guest(&self) -> bool {
+    return false;
+  }
+
+  fn render_config(&self) {}
+
+  fn start(&self) {}
+
+  fn stop(&self) {}
+
+  fn delete(&self) {}
+
+  fn status(&self) -> Status {
+    return Status::Running;
+  }
+
+  fn exec(&self, args: &[String]) -> bool {
+    let (program, rest) = args.split_first().expect("No command is given");
+
+    return process::Command::new(program).args(rest).status().expect(&format!("Failed to execute {:?}", args)).success();
+  }
+
+  fn ssh(&self) {
+    panic!("No VM is used by the backend `none`");
+  }
+}
@@ -0,0 +1,237 @@
+use crate::application;
+use crate::config;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// What has to be regenerated when the watched file is changed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Target {
+  Config,
+  /// The application TOML.
+  Application(String),
+  /// The compose file of the application.
+  Compose(String),
+  Mutagen,
+}
+
+type Snapshot = BTreeMap<PathBuf, (Target, Option<SystemTime>)>;
+
+pub struct Watcher {
+  interval: Duration,
+  debounce: Duration,
+  reload_dns: bool,
+  recreate: bool,
+}
+
+pub fn new(interval: Duration, debounce: Duration, reload_dns: bool, recreate: bool) -> Watcher {
+  return Watcher {
+    interval: interval,
+    debounce: debounce,
+    reload_dns: reload_dns,
+    recreate: recreate,
+  };
+}
+
+impl Watcher {
+  pub fn run(&self) {
+    self.regenerate(config::current().application_names().into_iter().map(Target::Compose).chain([Target::Mutagen]).collect());
+
+    let mut before = snapshot();
+
+    println!("Watching {} files", before.len());
+
+    loop {
+      thread::sleep(self.interval);
+
+      let mut after = snapshot();
+      let mut targets = changes(&before, &after);
+
+      if targets.is_empty() {
+        continue;
+      }
+
+      // Wait for the files to settle down. e.g. the editor saves some files at once.
+      loop {
+        thread::sleep(self.debounce);
+
+        let next = snapshot();
+        let more = changes(&after, &next);
+
+        after = next;
+
+        if more.is_empty() {
+          break;
+        }
+
+        targets.extend(more);
+      }
+
+      before = after;
+      self.regenerate(targets);
+    }
+  }
+
+  fn regenerate(&self, targets: BTreeSet<Target>) {
+    // The running watcher is kept with the current config if the new one is invalid, since the restarted one would die.
+    if targets.contains(&Target::Config) {
+      match config::try_load_from(config::current().root().as_ref(), None) {
+        Ok(_) => {
+          println!("Hills.toml is changed. Restarting...");
+          restart();
+        }
+        Err(e) => eprintln!("Warning: Hills.toml is changed but not reloaded : {}", e),
+      }
+    }
+
+    let mut updated: Vec<application::Application> = vec![];
+
+    for target in targets.iter() {
+      let (name, force) = match target {
+        Target::Application(name) => (name, true),
+        Target::Compose(name) => (name, false),
+        _ => continue,
+      };
+
+      if !application::is_exists(name) {
+        continue;
+      }
+
+      let app = match application::load(name) {
+        Ok(app) => app,
+        Err(e) => {
+          eprintln!("Skipped {} : {}", name, e);
+          continue;
+        }
+      };
+
+      if !app.compose_file_path().exists() {
+        eprintln!("Not found {:?}", app.compose_file_path());
+        continue;
+      }
+
+      if app.update(force) {
+        println!("Updated {}", app.name());
+        updated.push(app);
+      }
+    }
+
+    if targets.iter().any(|t| matches!(t, Target::Application(_) | Target::Mutagen)) {
+      config::current().mutagen().update();
+    }
+
+    if updated.is_empty() {
+      return;
+    }
+
+    if self.reload_dns {
+      config::current().dns().reload();
+    }
+
+    if self.recreate {
+      updated.iter().for_each(|app| app.up());
+    }
+  }
+}
+
+fn snapshot() -> Snapshot {
+  let c = config::current();
+  let mut files: Snapshot = BTreeMap::new();
+
+  watch(&mut files, &c.path(), Target::Config);
+
+  for path in files_in(&c.app_root()) {
+    let name = path.file_name().unwrap().to_str().unwrap().strip_suffix(".toml").map(|s| s.to_string());
+
+    if let Some(name) = name {
+      watch(&mut files, &path, Target::Application(name));
+    }
+  }
+
+  // The invalid applications are reported when their TOML is changed. They're skipped here because the snapshot is taken on every poll.
+  for name in c.application_names() {
+    let Ok(app) = application::load(&name) else {
+      continue;
+    };
+
+    for path in app.compose_file_paths() {
+      watch(&mut files, &path, Target::Compose(name.clone()));
+    }
+  }
+
+  for path in files_in(&c.mutagen().root()) {
+    watch(&mut files, &path, Target::Mutagen);
+  }
+
+  return files;
+}
+
+fn watch(files: &mut Snapshot, path: &Path, target: Target) {
+  let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+  files.insert(path.to_path_buf(), (target, modified));
+}
+
+fn files_in(dir: &Path) -> Vec<PathBuf> {
+  if !dir.exists() {
+    return vec![];
+  }
+
+  return fs::read_dir(dir).expect(&format!("Failed to read {:?}", dir)).map(|e| e.unwrap().path()).filter(|p| p.is_file()).collect();
+}
+
+/// Targets of the files that are added, removed or modified.
+fn changes(before: &Snapshot, after: &Snapshot) -> BTreeSet<Target> {
+  let mut targets: BTreeSet<Target> = BTreeSet::new();
+
+  for (path, (target, modified)) in after.iter() {
+    if before.get(path).map(|(_, m)| m) != Some(modified) {
+      targets.insert(target.clone());
+    }
+  }
+
+  for (path, (target, _)) in before.iter() {
+    if !after.contains_key(path) {
+      targets.insert(target.clone());
+    }
+  }
+
+  return targets;
+}
+
+fn restart() -> ! {
+  let exe = env::current_exe().expect("Failed to get the current executable");
+  let e = process::Command::new(exe).args(env::args().skip(1)).exec();
+
+  panic!("Failed to restart : {}", e);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn changes_detect_modified_added_and_removed_files() {
+    let t1 = Some(SystemTime::UNIX_EPOCH);
+    let t2 = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1));
+
+    let before: Snapshot = BTreeMap::from([
+      (PathBuf::from("Hills.toml"), (Target::Config, t1)),
+      (PathBuf::from("web/docker-compose.yml"), (Target::Compose("web".to_string()), t1)),
+      (PathBuf::from("mutagen/web.yml"), (Target::Mutagen, t1)),
+    ]);
+    let after: Snapshot = BTreeMap::from([
+      (PathBuf::from("Hills.toml"), (Target::Config, t1)),
+      (PathBuf::from("web/docker-compose.yml"), (Target::Compose("web".to_string()), t2)),
+      (PathBuf::from("applications/api.toml"), (Target::Application("api".to_string()), t1)),
+    ]);
+
+    assert_eq!(changes(&before, &after), BTreeSet::from([Target::Application("api".to_string()), Target::Compose("web".to_string()), Target::Mutagen]));
+    assert!(changes(&after, &after).is_empty());
+  }
+}
@@ -0,0 +1,75 @@
+use functions::hills;
+use predicates::prelude::*;
+use std::fs;
+use tempdir::TempDir;
+
+mod functions;
+
+fn setup_workspace() -> TempDir {
+    functions::setup_workspace(
+        "\n[network]\nsubnet = \"172.31.0.0/16\"\n",
+        &[
+            ("applications/shared.toml", "version = 1\npath = \"repos/shared\"\n"),
+            (
+                "applications/web.toml",
+                "version = 1\npath = \"repos/web\"\ndepends_on = [\"shared\"] # first\n\n[env]\nDB = '{{ app(\"shared\").service(\"db\").fqdn }}'\n",
+            ),
+        ],
+    )
+}
+
+#[test]
+fn add_and_remove_the_aliases() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    hills(&dir).args(["alias", "add", "shared", "db"]).assert().success();
+    hills(&dir).args(["alias", "add", "db", "pg"]).assert().success();
+    hills(&dir).args(["alias", "add", "web", "db"]).assert().failure().stderr(predicate::str::contains("db is already an alias of shared"));
+    hills(&dir).args(["alias", "add", "web", "shared"]).assert().failure().stderr(predicate::str::contains("shared is the name of the application"));
+    hills(&dir).args(["alias", "ls"]).assert().success().stdout("db -> shared\npg -> shared\n");
+    hills(&dir).args(["alias", "rm", "db"]).assert().success();
+    hills(&dir).args(["alias", "ls", "shared"]).assert().success().stdout("pg -> shared\n");
+
+    Ok(())
+}
+
+#[test]
+fn rename_the_application_with_the_references() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    functions::write_file(&dir.path().join("dns/subnets.toml"), "shared = \"172.31.2.0/24\"\n");
+    functions::write_file(&dir.path().join("env/shared.local.env"), "A=1\n");
+
+    hills(&dir).args(["alias", "add", "shared", "pg"]).assert().success();
+    hills(&dir).args(["alias", "rename-app", "pg", "web"]).assert().failure().stderr(predicate::str::contains("Already exists web"));
+    hills(&dir).args(["alias", "rename-app", "pg", "database"]).assert().success();
+
+    assert!(dir.path().join("applications/database.toml").exists());
+    assert!(dir.path().join("env/database.local.env").exists());
+    assert_eq!(fs::read_to_string(dir.path().join("dns/subnets.toml"))?, "database = \"172.31.2.0/24\"\n");
+    assert_eq!(
+        fs::read_to_string(dir.path().join("applications/web.toml"))?,
+        "version = 1\npath = \"repos/web\"\ndepends_on = [\"database\"] # first\n\n[env]\nDB = '{{ app(\"database\").service(\"db\").fqdn }}'\n"
+    );
+    hills(&dir).args(["alias", "ls"]).assert().success().stdout("pg -> database\n");
+
+    Ok(())
+}
+
+#[test]
+fn running_application_is_not_renamed() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    let path = functions::running_docker(&dir);
+
+    hills(&dir)
+        .env("PATH", path)
+        .args(["alias", "rename-app", "shared", "database"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("shared is running. Stop the containers first"));
+
+    assert!(dir.path().join("applications/shared.toml").exists());
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::fs;
+use std::process::Command;
+use tempdir::TempDir;
+
+mod functions;
+
+#[test]
+fn reject_invalid_size() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = functions::setup_cwd();
+
+    Command::cargo_bin("hills")?.arg("init").assert().success();
+
+    let f = dir.path().join("Hills.toml");
+    let s = fs::read_to_string(&f)?.replace("memory = \"8GB\"", "memory = \"8 gigabytes\"");
+    fs::write(&f, s)?;
+
+    Command::cargo_bin("hills")?.arg("list").assert().failure().stderr(predicate::str::contains("is not a size like 8GB"));
+
+    Ok(())
+}
+
+#[test]
+fn set_the_value_with_the_comments_preserved() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = TempDir::new("hills")?;
+    let f = dir.path().join("Hills.toml");
+    fs::write(&f, "# workspace\napp_root = \"applications\"\n\n[lima]\ncpus = 2 # cores\n\n[engine]\nparallel = 2\n")?;
+
+    Command::cargo_bin("hills")?.current_dir(dir.path()).args(["config", "set", "lima.cpus", "4"]).assert().success();
+    Command::cargo_bin("hills")?.current_dir(dir.path()).args(["config", "get", "lima.cpus"]).assert().success().stdout("4\n");
+    Command::cargo_bin("hills")?
+        .current_dir(dir.path())
+        .args(["config", "set", "lima.memory", "8 gigabytes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a size like 8GB"));
+    Command::cargo_bin("hills")?.current_dir(dir.path()).args(["config", "set", "lima.cpu", "4"]).assert().failure().stderr(predicate::str::contains("Unknown key lima.cpu"));
+    Command::cargo_bin("hills")?.current_dir(dir.path()).args(["config", "unset", "engine.parallel"]).assert().success();
+
+    assert_eq!(fs::read_to_string(&f)?, "# workspace\napp_root = \"applications\"\n\n[lima]\ncpus = 4 # cores\n\n[engine]\n");
+
+    Ok(())
+}
+
+#[test]
+fn reject_unsupported_arch_of_the_image() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = functions::setup_workspace("\n[[lima.images.sources]]\narch = \"riscv64\"\nlocation = \"/srv/images/ubuntu.img\"\n", &[]);
+
+    functions::hills(&dir).arg("list").assert().failure().stderr(predicate::str::contains("Unsupported architecture `riscv64`"));
+
+    Ok(())
+}
+
+#[test]
+fn show_the_file_of_the_user_layer() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = functions::setup_workspace("", &[("xdg/hills/config.toml", "[lima]\ncpus = 6\n")]);
+
+    functions::hills(&dir)
+        .env("XDG_CONFIG_HOME", dir.path().join("xdg"))
+        .args(["config", "show", "--origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("lima.cpus = 6\t# {}\n", dir.path().join("xdg/hills/config.toml").display())));
+
+    Ok(())
+}
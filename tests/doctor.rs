@@ -0,0 +1,52 @@
+use functions::hills;
+use predicates::prelude::*;
+
+mod functions;
+
+#[test]
+fn warn_when_the_running_applications_exceed_the_vm() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = functions::setup_workspace(
+        "",
+        &[
+            (
+                "applications/web.toml",
+                "version = 1\npath = \"repos/web\"\n\n[resources]\ncpus = 1.5\nmem_limit = \"512m\"\n\n[resources.services.worker]\nmem_limit = \"1g\"\n",
+            ),
+            ("repos/web/docker-compose.yml", "services:\n  app:\n    image: app\n  worker:\n    image: app\n"),
+        ],
+    );
+    let path = functions::running_docker(&dir);
+
+    hills(&dir)
+        .env("PATH", path)
+        .arg("doctor")
+        .assert()
+        .failure()
+        .stdout("VM : 2 cpus, 8.0GiB\nweb : 3 cpus, 1.5GiB\n")
+        .stderr(predicate::str::contains("The total cpus 3 of the running applications exceeds 2 of the VM"));
+
+    Ok(())
+}
+
+#[test]
+fn inform_the_services_without_the_limits() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = functions::setup_workspace(
+        "",
+        &[
+            ("applications/web.toml", "version = 1\npath = \"repos/web\"\n\n[resources.services.app]\ncpus = 1.0\nmem_limit = \"1g\"\n"),
+            ("applications/broken.toml", "version = 1\n"),
+            ("repos/web/docker-compose.yml", "services:\n  app:\n    image: app\n  worker:\n    image: app\n"),
+        ],
+    );
+    let path = functions::running_docker(&dir);
+
+    hills(&dir)
+        .env("PATH", path)
+        .arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Note: worker of web have no cpus or mem_limit"))
+        .stderr(predicate::str::contains("Skipped broken").and(predicate::str::contains("Warning").not()));
+
+    Ok(())
+}
@@ -1,7 +1,11 @@
-use hills::config::Config;
+#![allow(dead_code)]
+
+use assert_cmd::Command;
 use std::env;
 use std::fs::{self, File};
 use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
 use tempdir::TempDir;
 
 pub fn setup_cwd() -> TempDir {
@@ -18,3 +22,44 @@ pub fn setup_config(dir: &TempDir) {
     write!(fs, "{}", s).unwrap();
     fs.flush().unwrap();
 }
+
+/// Workspace with `applications` as the app root. The config is appended to Hills.toml, and the files are written relative to the root.
+/// A path that ends with `/` is created as a directory.
+pub fn setup_workspace(config: &str, files: &[(&str, &str)]) -> TempDir {
+    let dir = TempDir::new("hills").unwrap();
+
+    fs::write(dir.path().join("Hills.toml"), format!("app_root = \"applications\"\n{}", config)).unwrap();
+    fs::create_dir_all(dir.path().join("applications")).unwrap();
+
+    for (path, content) in files {
+        write_file(&dir.path().join(path), content);
+    }
+
+    dir
+}
+
+pub fn write_file(path: &Path, content: &str) {
+    if path.to_str().unwrap().ends_with('/') {
+        fs::create_dir_all(path).unwrap();
+    } else {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+}
+
+/// Put `bin/docker` that reports every project is running. It returns PATH that has it first.
+pub fn running_docker(dir: &TempDir) -> String {
+    let bin = dir.path().join("bin");
+
+    write_file(&bin.join("docker"), "#!/bin/sh\necho 0123456789ab\n");
+    fs::set_permissions(bin.join("docker"), fs::Permissions::from_mode(0o755)).unwrap();
+
+    format!("{}:/usr/bin:/bin", bin.display())
+}
+
+/// hills run in the workspace.
+pub fn hills(dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("hills").unwrap();
+    cmd.current_dir(dir.path());
+    cmd
+}
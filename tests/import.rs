@@ -0,0 +1,90 @@
+use assert_cmd::prelude::*;
+use functions::hills;
+use predicates::prelude::*;
+use std::fs;
+use std::process::Command;
+use tempdir::TempDir;
+
+mod functions;
+
+fn setup_workspace() -> TempDir {
+    let dir = functions::setup_workspace(
+        "",
+        &[
+            (
+                "repos/web/compose.yaml",
+                "services:\n  app:\n    image: nginx\n    ports:\n      - \"8080:80\"\n  db:\n    image: postgres\n    network_mode: host\n",
+            ),
+            ("repos/api/docker-compose.yml", "services:\n  api:\n    image: api\n    expose:\n      - 3000\n"),
+            ("repos/docs/", ""),
+        ],
+    );
+
+    for repo in ["web", "api", "docs"] {
+        let path = dir.path().join("repos").join(repo);
+
+        Command::new("git").arg("init").arg("-q").arg(&path).assert().success();
+        Command::new("git").arg("-C").arg(&path).args(["remote", "add", "origin", &format!("git@example.com:org/{}.git", repo)]).assert().success();
+    }
+
+    dir
+}
+
+#[test]
+fn import_the_checkout() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    hills(&dir)
+        .args(["import", "repos/web", "--name", "shop"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("app : 8080:80"))
+        .stderr(predicate::str::contains("`network_mode` of db is not supported"));
+
+    let s = fs::read_to_string(dir.path().join("applications/shop.toml"))?;
+
+    assert!(s.contains("path = \"repos/web\""));
+    assert!(s.contains("repository = \"git@example.com:org/web.git\""));
+    assert!(s.contains("[compose]\nfiles = [\"compose.yaml\"]"));
+
+    hills(&dir).args(["import", "repos/web"]).assert().failure().stderr(predicate::str::contains("is already imported as shop"));
+
+    Ok(())
+}
+
+#[test]
+fn import_every_repository_under_the_directory() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    hills(&dir).args(["import", "--scan", "repos"]).assert().success().stderr(predicate::str::contains("Not found compose files"));
+
+    assert!(dir.path().join("applications/api.toml").exists());
+    assert!(dir.path().join("applications/web.toml").exists());
+    assert!(!dir.path().join("applications/docs.toml").exists());
+    assert!(!fs::read_to_string(dir.path().join("applications/api.toml"))?.contains("\n[compose]"));
+
+    Ok(())
+}
+
+#[test]
+fn scan_the_root_except_for_the_imported_repositories() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    Command::new("git").arg("init").arg("-q").arg(dir.path()).assert().success();
+    Command::new("git").arg("-C").arg(dir.path().join("repos/api")).args(["remote", "remove", "origin"]).assert().success();
+
+    hills(&dir).args(["import", "repos/web"]).assert().success();
+    hills(&dir)
+        .args(["import", "--scan", "."])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Skipped").count(1))
+        .stderr(predicate::str::contains("Not found compose files"));
+
+    let s = fs::read_to_string(dir.path().join("applications/api.toml"))?;
+
+    assert!(s.contains("\n# repository = \"\"\n"));
+    assert!(!s.contains("\nrepository ="));
+
+    Ok(())
+}
@@ -1,6 +1,5 @@
 use assert_cmd::prelude::*;
-use predicates::prelude::*;
-use std::fs::{self, File};
+use std::fs;
 use std::process::Command;
 
 mod functions;
@@ -18,7 +17,37 @@ fn init_if_the_file_absent() -> Result<(), Box<dyn std::error::Error>> {
 
     let s = fs::read_to_string(f)?;
 
-    let expected = "app_root = \"applications\"\n";
+    let expected = r#"version = 1
+app_root = "applications"
+
+[vm]
+backend = "lima"
+
+[engine]
+kind = "docker"
+
+[network]
+root = "dns"
+name = "hills"
+domain = "local"
+dns = "8.8.8.8"
+subnet = "172.31.0.0/16"
+
+[lima]
+root = "lima"
+vm_type = "qemu"
+cpus = 2
+memory = "8GB"
+disk = "30GB"
+ssh_port = 2222
+
+[lima.provision]
+timezone = "Etc/UTC"
+packages = ["build-essential", "uidmap", "dbus-user-session", "net-tools"]
+
+[mutagen]
+root = "mutagen"
+"#;
 
     assert_eq!(s, expected);
 
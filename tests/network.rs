@@ -0,0 +1,52 @@
+use functions::hills;
+use predicates::prelude::*;
+
+mod functions;
+
+#[test]
+fn show_the_reachability_and_the_rules() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = functions::setup_workspace(
+        "\n[network]\nsubnet = \"172.31.0.0/16\"\n\n[aliases]\ngw = \"gateway\"\n",
+        &[
+            ("applications/gateway.toml", "version = 1\npath = \"repos/gateway\"\n"),
+            ("applications/web.toml", "version = 1\npath = \"repos/web\"\nallow_from = [\"gw\", \"admin\"]\n"),
+            ("applications/worker.toml", "version = 1\npath = \"repos/worker\"\n"),
+            ("dns/subnets.toml", "gateway = \"172.31.1.0/24\"\nweb = \"172.31.2.0/24\"\nworker = \"172.31.3.0/24\"\n"),
+        ],
+    );
+
+    hills(&dir)
+        .args(["network", "policy", "show"])
+        .assert()
+        .success()
+        .stdout("FROM \\ TO  gateway  web    worker\ngateway    allow    allow  allow\nweb        allow    allow  allow\nworker     allow    deny   allow\n");
+    hills(&dir)
+        .args(["network", "policy", "show", "--rules"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("iptables -A HILLS-POLICY -s 172.31.3.0/24 -d 172.31.2.0/24 -j DROP\n"))
+        .stdout(predicate::str::contains("-s 172.31.1.0/24").not());
+    hills(&dir).args(["validate", "web"]).assert().failure().stderr(predicate::str::contains("allow_from : Not found application admin"));
+
+    Ok(())
+}
+
+#[test]
+fn skip_the_policy_without_the_subnets() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = functions::setup_workspace(
+        "\n[network]\nsubnet = \"172.31.0.0/16\"\n",
+        &[
+            ("applications/web.toml", "version = 1\npath = \"repos/web\"\nallow_from = [\"worker\"]\n"),
+            ("applications/worker.toml", "version = 1\npath = \"repos/worker\"\n"),
+            ("applications/broken.toml", "version = 1\n"),
+        ],
+    );
+
+    hills(&dir)
+        .args(["network", "policy", "apply"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Skipped broken").and(predicate::str::contains("No subnets are assigned yet")));
+
+    Ok(())
+}
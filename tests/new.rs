@@ -1,8 +1,3 @@
-use assert_cmd::prelude::*;
-use predicates::prelude::*;
-use std::fs::{self, File};
-use std::process::Command;
-
 mod functions;
 
 // #[test]
@@ -25,3 +20,52 @@ mod functions;
 
 //     Ok(())
 // }
+
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::process::Command;
+use tempdir::TempDir;
+
+fn setup_template(dir: &TempDir) -> Result<(), Box<dyn std::error::Error>> {
+    let template = dir.path().join("templates/rails");
+
+    fs::create_dir_all(template.join("files/bin"))?;
+    fs::write(
+        template.join("application.toml"),
+        "version = 1\npath = \"repos/{{ name }}\"\n\n[hooks]\npre_up = [\"./bin/setup\"]\n\n[env]\nAPP_URL = \"https://{{ domain }}\"\nDB = '{{ app(\"shared\").service(\"db\").fqdn }}'\n",
+    )?;
+    fs::write(template.join("files/docker-compose.yml"), "services:\n  app:\n    image: ruby:{{ ruby }}\n")?;
+    fs::write(template.join("files/bin/setup"), "#!/bin/sh\nbundle install\n")?;
+    fs::set_permissions(template.join("files/bin/setup"), fs::Permissions::from_mode(0o755))?;
+
+    Ok(())
+}
+
+#[test]
+fn new_from_the_template() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = TempDir::new("hills")?;
+    functions::setup_config(&dir);
+    setup_template(&dir)?;
+
+    Command::cargo_bin("hills")?.current_dir(dir.path()).args(["new", "--list-templates"]).assert().success().stdout("rails\n");
+    Command::cargo_bin("hills")?.current_dir(dir.path()).args(["new", "My_Api"]).assert().failure().stderr(predicate::str::contains("has to be lowercase letters"));
+    assert!(!dir.path().join("applications/My_Api.toml").exists());
+    Command::cargo_bin("hills")?
+        .current_dir(dir.path())
+        .args(["new", "api", "--template", "rails"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown variable `ruby`"));
+    Command::cargo_bin("hills")?.current_dir(dir.path()).args(["new", "api", "--template", "rails", "--var", "ruby=3.3"]).assert().success();
+
+    assert_eq!(
+        fs::read_to_string(dir.path().join("applications/api.toml"))?,
+        "version = 1\npath = \"repos/api\"\n\n[hooks]\npre_up = [\"./bin/setup\"]\n\n[env]\nAPP_URL = \"https://api.local\"\nDB = '{{ app(\"shared\").service(\"db\").fqdn }}'\n"
+    );
+    assert_eq!(fs::read_to_string(dir.path().join("repos/api/docker-compose.yml"))?, "services:\n  app:\n    image: ruby:3.3\n");
+    assert_eq!(fs::metadata(dir.path().join("repos/api/bin/setup"))?.permissions().mode() & 0o777, 0o755);
+
+    Ok(())
+}
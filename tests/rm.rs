@@ -0,0 +1,85 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempdir::TempDir;
+
+mod functions;
+
+fn setup_workspace() -> TempDir {
+    functions::setup_workspace(
+        "\n[network]\nsubnet = \"172.31.0.0/16\"\n\n[aliases]\ndb = \"shared\"\n",
+        &[
+            ("applications/shared.toml", "version = 1\npath = \"repos/shared\"\n"),
+            ("applications/web.toml", "version = 1\npath = \"repos/web\"\ndepends_on = [\"shared\"]\n"),
+            ("applications/api.toml", "version = 1\npath = \"repos/api\"\nuses = [\"db\"]\nallow_from = [\"db\"]\n"),
+            ("repos/shared/", ""),
+            ("dns/subnets.toml", "shared = \"172.31.2.0/24\"\nweb = \"172.31.1.0/24\"\n"),
+        ],
+    )
+}
+
+/// hills without docker in PATH.
+fn hills(dir: &TempDir) -> Command {
+    let mut cmd = functions::hills(dir);
+    cmd.env("PATH", "/usr/bin:/bin");
+    cmd
+}
+
+#[test]
+fn keep_the_application_unless_confirmed() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    hills(&dir).args(["rm", "shared"]).write_stdin("n\n").assert().failure().stdout(predicate::str::contains("Remove the aliases db"));
+
+    assert!(dir.path().join("applications/shared.toml").exists());
+
+    Ok(())
+}
+
+#[test]
+fn remove_the_application_and_the_generated_files() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    hills(&dir)
+        .args(["rm", "db", "--purge-repo"])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("web still depends on shared"))
+        .stderr(predicate::str::contains("api still uses shared"))
+        .stderr(predicate::str::contains("api still allows shared in allow_from"));
+
+    assert!(!dir.path().join("applications/shared.toml").exists());
+    assert!(!dir.path().join("repos/shared").exists());
+    assert_eq!(fs::read_to_string(dir.path().join("dns/subnets.toml"))?, "web = \"172.31.1.0/24\"\n");
+    assert_eq!(fs::read_to_string(dir.path().join("Hills.toml"))?, "app_root = \"applications\"\n\n[network]\nsubnet = \"172.31.0.0/16\"\n");
+
+    Ok(())
+}
+
+#[test]
+fn keep_the_repository_that_contains_the_others() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    fs::write(dir.path().join("applications/web.toml"), "version = 1\npath = \"repos/web/../shared/web\"\n")?;
+
+    hills(&dir)
+        .args(["rm", "shared", "--purge-repo", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("contains the repository of web"));
+
+    assert!(dir.path().join("applications/shared.toml").exists());
+    assert!(dir.path().join("repos/shared").exists());
+
+    fs::write(dir.path().join("applications/web.toml"), "version = 1\npath = \"repos/web\"\n")?;
+
+    hills(&dir)
+        .args(["rm", "shared", "--purge-repo"])
+        .write_stdin("n\n")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(format!("Delete the repository {:?}\n", dir.path().join("repos/shared"))));
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use functions::hills;
+use predicates::prelude::*;
+use tempdir::TempDir;
+
+mod functions;
+
+fn setup_workspace() -> TempDir {
+    functions::setup_workspace(
+        "",
+        &[
+            ("repos/foo/src/", ""),
+            ("repos/foo/docker-compose.yml", "services:\n  web:\n    image: nginx\n"),
+            ("applications/foo.toml", "version = 1\npath = \"repos/foo\"\n"),
+        ],
+    )
+}
+
+#[test]
+fn find_the_config_in_the_parent_directories() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    hills(&dir).current_dir(dir.path().join("repos/foo/src")).arg("list").assert().success().stdout("foo\n");
+
+    Ok(())
+}
+
+#[test]
+fn find_the_config_in_hills_root() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+    let other = TempDir::new("other")?;
+
+    hills(&dir).current_dir(other.path()).env("HILLS_ROOT", dir.path()).arg("list").assert().success().stdout("foo\n");
+
+    Ok(())
+}
+
+#[test]
+fn infer_the_application_from_the_current_directory() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    hills(&dir).current_dir(dir.path().join("repos/foo/src")).arg("validate").assert().success().stdout("foo : OK\n");
+    hills(&dir).arg("validate").assert().failure().stderr(predicate::str::contains("isn't in any application"));
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use functions::hills;
+use predicates::prelude::*;
+use tempdir::TempDir;
+
+mod functions;
+
+fn setup_workspace() -> TempDir {
+    functions::setup_workspace(
+        "\n[network]\nsubnet = \"172.31.0.0/16\"\n",
+        &[
+            ("applications/shared.toml", "version = 1\npath = \"repos/shared\"\nshared = true\n"),
+            ("applications/cache.toml", "version = 1\npath = \"repos/cache\"\n"),
+            ("applications/web.toml", "version = 1\npath = \"repos/web\"\nuses = [\"shared\", \"cache\", \"queue\"]\n"),
+            ("dns/leases.toml", "[shared.postgres]\nipv4 = \"172.31.2.1\"\n\n[web.app]\nipv4 = \"172.31.1.1\"\n"),
+        ],
+    )
+}
+
+#[test]
+fn consumers_use_only_the_shared_applications() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    hills(&dir)
+        .args(["validate", "web"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("uses : cache is not shared").and(predicate::str::contains("uses : Not found application queue")));
+
+    Ok(())
+}
+
+#[test]
+fn shared_services_are_published_under_the_workspace_domain() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    hills(&dir)
+        .arg("hosts")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("172.31.2.1\tpostgres.shared.local").and(predicate::str::contains("172.31.2.1\tpostgres.local")))
+        .stdout(predicate::str::contains("app.local").not());
+
+    Ok(())
+}
+
+#[test]
+fn shared_services_are_not_named_after_the_applications() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    functions::write_file(&dir.path().join("repos/shared/docker-compose.yml"), "services:\n  web:\n    image: nginx\n");
+
+    hills(&dir)
+        .args(["update", "shared"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("web.local is the name of the application"));
+
+    Ok(())
+}
+
+#[test]
+fn records_are_in_the_domains_of_the_application() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = setup_workspace();
+
+    functions::write_file(
+        &dir.path().join("applications/web.toml"),
+        "version = 1\npath = \"repos/web\"\n\n[[dns.records]]\nname = \"postgres.local.\"\ntype = \"A\"\nvalue = \"172.31.1.9\"\n\n[[dns.records]]\nname = \"api.web.local.\"\ntype = \"A\"\nvalue = \"172.31.1.10\"\n",
+    );
+
+    hills(&dir)
+        .args(["validate", "web"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("dns.records : postgres.local. is out of the domains web.local").and(predicate::str::contains("api.web.local").not()));
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use functions::hills;
+use predicates::prelude::*;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+mod functions;
+
+#[test]
+fn blank_the_secrets_when_up_fails() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = functions::setup_workspace(
+        "\n[network]\nsubnet = \"172.31.0.0/16\"\n",
+        &[
+            ("applications/web.toml", "version = 1\npath = \"repos/web\"\n"),
+            ("repos/web/docker-compose.yml", "services:\n  app:\n    image: nginx\n"),
+            ("bin/docker", "#!/bin/sh\ncase \"$*\" in *\" up\"*) exit 1;; esac\n"),
+        ],
+    );
+
+    fs::set_permissions(dir.path().join("bin/docker"), fs::Permissions::from_mode(0o755))?;
+
+    let run = |args: &[&str]| {
+        let mut cmd = hills(&dir);
+        cmd.env("PATH", format!("{}:/usr/bin:/bin", dir.path().join("bin").display()));
+        cmd.env("XDG_CONFIG_HOME", dir.path().join("xdg")).env("HILLS_VM__BACKEND", "none").args(args);
+        cmd
+    };
+
+    run(&["env", "set", "--layer", "secret", "web", "TOKEN", "abc"]).assert().success();
+    run(&["up", "web"]).assert().failure().stderr(predicate::str::contains("Failed to up web"));
+
+    assert_eq!(fs::read_to_string(dir.path().join(".dist/web/secret.env"))?, "");
+
+    Ok(())
+}